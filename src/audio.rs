@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use log::debug;
+
+use crate::{
+    file_ops::FileDescriptor,
+    folder_ops::FolderDescriptor,
+    pcloud_client::PCloudClient,
+    pcloud_model::{self, DownloadLink, FileCategory, Metadata},
+};
+
+/// Number of audio links resolved concurrently when building a playlist
+const PLAYLIST_CONCURRENCY: usize = 4;
+
+pub struct AudioLinkRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    ///  ID of the  file
+    file_id: Option<u64>,
+    /// Path to the  file
+    path: Option<String>,
+    /// audio bit rate in kilobits, from 16 to 320
+    abitrate: Option<u32>,
+    /// If set, the content-type will be 'application/octet-stream', if not - 'audio/mpeg'
+    force_download: bool,
+}
+
+#[allow(dead_code)]
+impl AudioLinkRequestBuilder {
+    pub(crate) fn for_file<T: FileDescriptor>(
+        client: &PCloudClient,
+        file_like: T,
+    ) -> Result<AudioLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let f = file_like.to_file()?;
+
+        if !f.is_empty() {
+            Ok(AudioLinkRequestBuilder {
+                file_id: f.file_id,
+                path: f.path,
+                client: client.clone(),
+                abitrate: None,
+                force_download: false,
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// audio bit rate in kilobits, from 16 to 320. Fails with an InvalidBitrateError for other values.
+    pub fn with_bitrate(
+        mut self,
+        value: u32,
+    ) -> Result<AudioLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        if !(16..=320).contains(&value) {
+            Err(pcloud_model::InvalidBitrateError { bitrate: value })?
+        }
+        self.abitrate = Some(value);
+        Ok(self)
+    }
+
+    /// If set, the content-type will be 'application/octet-stream', if not - 'audio/mpeg'
+    pub fn force_download(mut self, value: bool) -> AudioLinkRequestBuilder {
+        self.force_download = value;
+        self
+    }
+
+    /// Fetch the link to stream the audio file (transcoded to mp3)
+    pub async fn get(self) -> Result<DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/getaudiolink", self.client.api_host));
+
+        if let Some(id) = self.file_id {
+            debug!("Requesting audio link for file {}", id);
+            r = r.query(&[("fileid", id)]);
+        }
+
+        if let Some(p) = self.path {
+            debug!("Requesting audio link for file {}", p);
+            r = r.query(&[("path", p)]);
+        }
+
+        if let Some(v) = self.abitrate {
+            r = r.query(&[("abitrate", v)]);
+        }
+
+        if self.force_download {
+            r = r.query(&[("forcedownload", "1")]);
+        }
+
+        let link = self.client.execute_json::<DownloadLink>(r).await?;
+        Ok(link)
+    }
+}
+
+/// Order of the entries of an audio playlist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistOrder {
+    /// Order by the track number, files without track number are placed at the end (ordered by name)
+    TrackNo,
+    /// Order by the title, files without title are ordered by their file name
+    Title,
+    /// Order by the file name
+    Name,
+}
+
+impl PlaylistOrder {
+    /// Track numbers are sometimes given as `3/12`, so only the leading number is used
+    fn track_number(metadata: &Metadata) -> Option<u32> {
+        metadata.trackno.as_ref().and_then(|t| {
+            let digits: String = t
+                .trim()
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.parse().ok()
+        })
+    }
+
+    /// Compares two audio files according to this order
+    fn compare(&self, a: &Metadata, b: &Metadata) -> Ordering {
+        match self {
+            PlaylistOrder::TrackNo => {
+                match (
+                    PlaylistOrder::track_number(a),
+                    PlaylistOrder::track_number(b),
+                ) {
+                    (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.name.cmp(&b.name)),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => a.name.cmp(&b.name),
+                }
+            }
+            PlaylistOrder::Title => {
+                let x = a.title.as_ref().unwrap_or(&a.name);
+                let y = b.title.as_ref().unwrap_or(&b.name);
+                x.cmp(y)
+            }
+            PlaylistOrder::Name => a.name.cmp(&b.name),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PCloudClient {
+    /// Returns a link to stream the given audio file (transcoded to mp3). Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub fn get_audio_link<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<AudioLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        AudioLinkRequestBuilder::for_file(self, file_like)
+    }
+
+    /// Lists all audio files directly within the given folder and resolves an audio link for each of them. The entries are returned in the requested order. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub async fn audio_playlist<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+        order: PlaylistOrder,
+    ) -> Result<Vec<(Metadata, DownloadLink)>, Box<dyn std::error::Error + Send + Sync>> {
+        let listing = self
+            .list_folder(folder_like)?
+            .filter_category(FileCategory::Audio)
+            .get()
+            .await?;
+
+        let mut files: Vec<Metadata> = listing.metadata.map(|m| m.contents).unwrap_or_default();
+        files.sort_by(|a, b| order.compare(a, b));
+
+        // Resolve the links concurrently, but keep the order of the files
+        let playlist = stream::iter(files.into_iter().map(|file| async move {
+            let link = match file.fileid {
+                Some(file_id) => self.get_audio_link(file_id)?.get().await?,
+                None => Err(pcloud_model::PCloudResult::InvalidFileId)?,
+            };
+            Ok::<(Metadata, DownloadLink), Box<dyn std::error::Error + Send + Sync>>((file, link))
+        }))
+        .buffered(PLAYLIST_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+        Ok(playlist)
+    }
+}
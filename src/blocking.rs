@@ -0,0 +1,172 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    file_ops::FileDescriptor,
+    folder_ops::FolderDescriptor,
+    pcloud_client,
+    pcloud_model::{
+        FileOrFolderStat, FolderRecursivlyDeleted, LogoutResponse, UploadedFile, UserInfo,
+    },
+};
+
+/// Blocking (synchronous) facade of the pCloud client for consumers, which do not want to set up an async runtime themselves (like CLI tools and scripts). All requests are executed on a dedicated internal runtime. Like the blocking client of reqwest it must not be used (or dropped) within an async runtime.
+/// Only the most common operations are wrapped, any other operation of the async client can be executed with block_on().
+#[derive(Clone)]
+pub struct PCloudClient {
+    /// Async client to actually perform the requests
+    inner: pcloud_client::PCloudClient,
+    /// Runtime to execute the requests on, shared between all clones of this instance
+    runtime: Arc<Runtime>,
+}
+
+#[allow(dead_code)]
+impl PCloudClient {
+    /// Creates the internal runtime
+    fn runtime() -> Result<Runtime, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        Ok(runtime)
+    }
+
+    /// Creates a new PCloudClient instance with an already present OAuth 2.0 authentication token. Automatically determines nearest API server for best performance
+    pub fn with_oauth(
+        host: &str,
+        oauth2: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = PCloudClient::runtime()?;
+        let inner = runtime.block_on(pcloud_client::PCloudClient::with_oauth(host, oauth2))?;
+
+        Ok(PCloudClient {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Creates a new PCloudClient instance using username and password to obtain a temporary auth token. Token is shared between all clones of this instance and revoked when the last instance is dropped. Automatically determines nearest API server for best performance.
+    pub fn with_username_and_password(
+        host: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = PCloudClient::runtime()?;
+        let inner = runtime.block_on(pcloud_client::PCloudClient::with_username_and_password(
+            host, username, password,
+        ))?;
+
+        Ok(PCloudClient {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Creates a new PCloudClient instance without any authentication. Such a client can only be used to access public resources, like public file links. Automatically determines nearest API server for best performance.
+    pub fn anonymous(host: &str) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = PCloudClient::runtime()?;
+        let inner = runtime.block_on(pcloud_client::PCloudClient::anonymous(host))?;
+
+        Ok(PCloudClient {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The wrapped async client, to build requests which are not wrapped by this facade
+    pub fn as_async(&self) -> &pcloud_client::PCloudClient {
+        &self.inner
+    }
+
+    /// Executes any operation of the async client on the internal runtime and blocks until it is finished, e.g. `client.block_on(client.as_async().list_folder(0)?.recursive(true).get())`
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Returns information about the current user
+    pub fn get_user_info(&self) -> Result<UserInfo, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.get_user_info())
+    }
+
+    /// Lists the direct contents of a folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn list_folder<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.list_folder(folder_like)?.get())
+    }
+
+    /// Creates a new folder in a parent folder, if it does not exist yet. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn create_folder<T: FolderDescriptor>(
+        &self,
+        parent_folder_like: T,
+        name: &str,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(
+            self.inner
+                .create_folder(parent_folder_like, name)?
+                .execute(),
+        )
+    }
+
+    /// Deletes a folder with all its contents. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn delete_folder_recursive<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<FolderRecursivlyDeleted, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.delete_folder(folder_like)?.delete_recursive())
+    }
+
+    /// Checks if the folder exists. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn folder_exists<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.folder_exists(folder_like))
+    }
+
+    /// Uploads a single file with the given content into a folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn upload_file<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+        file_name: &str,
+        content: Vec<u8>,
+    ) -> Result<UploadedFile, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(
+            self.inner
+                .upload_file_into_folder(folder_like)?
+                .with_file(file_name, content)
+                .upload(),
+        )
+    }
+
+    /// Reads the content of a (small) file into memory. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub fn read_file_bytes<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.read_file_bytes(file_like))
+    }
+
+    /// Deletes a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub fn delete_file<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.delete_file(file_like))
+    }
+
+    /// Checks if the file exists. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub fn file_exists<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        self.block_on(self.inner.file_exists(file_like))
+    }
+
+    /// Explicitly revokes the auth token of the session (see the async client for details)
+    pub fn logout(self) -> Result<LogoutResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = self.runtime;
+        runtime.block_on(self.inner.logout())
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use log::{debug, warn};
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
+};
+
+use crate::{
+    folder_ops::{FolderDescriptor, PCloudFolder},
+    pcloud_client::PCloudClient,
+    pcloud_model::{self, Metadata, PCloudResult},
+};
+
+/// Default number of files uploaded concurrently
+const DEFAULT_UPLOAD_CONCURRENCY: usize = 4;
+
+/// Overall progress of a directory upload
+#[derive(Debug, Clone)]
+pub struct DirUploadProgress {
+    /// Number of files uploaded so far
+    pub files_done: u64,
+    /// Total number of files to upload
+    pub files_total: u64,
+    /// Number of bytes uploaded so far (only completed files are counted)
+    pub bytes_done: u64,
+    /// Total number of bytes to upload
+    pub bytes_total: u64,
+    /// Path (relative to the uploaded directory) of the file completed last
+    pub current: String,
+}
+
+/// Result of a directory upload
+#[derive(Debug)]
+pub struct UploadedDirectory {
+    /// Id of the target folder
+    pub folder_id: u64,
+    /// Number of folders created (or already existing) below the target folder
+    pub folders: u64,
+    /// Metadata of all uploaded files
+    pub files: Vec<Metadata>,
+}
+
+impl UploadedDirectory {
+    /// Total size of all uploaded files in bytes
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().filter_map(|m| m.size).sum()
+    }
+}
+
+/// Local file to upload
+struct LocalFile {
+    /// Path of the local file
+    path: PathBuf,
+    /// Path relative to the uploaded directory (only for progress reporting)
+    relative: String,
+    /// Size of the file in bytes
+    size: u64,
+    /// Id of the pCloud folder to upload the file into
+    folder_id: u64,
+}
+
+pub struct DirectoryUploadRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    /// Target folder
+    target: PCloudFolder,
+    /// Local directory to upload
+    local_dir: PathBuf,
+    /// Number of files uploaded concurrently
+    concurrency: usize,
+}
+
+#[allow(dead_code)]
+impl DirectoryUploadRequestBuilder {
+    pub(crate) fn into_folder<T: FolderDescriptor, P: AsRef<Path>>(
+        client: &PCloudClient,
+        folder_like: T,
+        local_dir: P,
+    ) -> Result<DirectoryUploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let f = folder_like.to_folder()?;
+
+        if !f.is_empty() || f.is_child() {
+            Ok(DirectoryUploadRequestBuilder {
+                client: client.clone(),
+                target: f,
+                local_dir: local_dir.as_ref().to_path_buf(),
+                concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// Number of files uploaded concurrently (defaults to 4)
+    pub fn concurrency(mut self, value: usize) -> DirectoryUploadRequestBuilder {
+        self.concurrency = value.max(1);
+        self
+    }
+
+    /// Uploads the directory
+    pub async fn upload(
+        self,
+    ) -> Result<UploadedDirectory, Box<dyn std::error::Error + Send + Sync>> {
+        self.run(None).await
+    }
+
+    /// Starts uploading the directory in the background and notifies the user of the progress after each uploaded file. If the receiver does not keep up, intermediate updates are skipped. The handle returns the result once the upload is finished.
+    pub fn upload_with_progress(
+        self,
+    ) -> (
+        JoinHandle<Result<UploadedDirectory, Box<dyn std::error::Error + Send + Sync>>>,
+        Receiver<DirUploadProgress>,
+    ) {
+        let (tx, rx) = mpsc::channel::<DirUploadProgress>(32);
+
+        let handle = tokio::spawn(self.run(Some(tx)));
+
+        (handle, rx)
+    }
+
+    /// Walks the local directory, creates the folder structure and uploads all files
+    async fn run(
+        self,
+        progress: Option<Sender<DirUploadProgress>>,
+    ) -> Result<UploadedDirectory, Box<dyn std::error::Error + Send + Sync>> {
+        let client = self.client;
+        let root_id = client.get_folder_id(self.target).await?;
+
+        // Folders are created top-down while walking, files are collected for the concurrent upload
+        let mut files: Vec<LocalFile> = Vec::new();
+        let mut folders: u64 = 0;
+        let mut pending: Vec<(PathBuf, u64)> = vec![(self.local_dir.clone(), root_id)];
+        // Symbolic links are followed, so every directory is entered only once to not recurse forever on links to an ancestor
+        let mut visited: HashSet<PathBuf> = HashSet::new();
+        visited.insert(tokio::fs::canonicalize(&self.local_dir).await?);
+
+        while let Some((dir, folder_id)) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let name = match entry.file_name().into_string() {
+                    Ok(name) => name,
+                    Err(_) => {
+                        warn!("Skipping {}, name is not valid UTF-8", path.display());
+                        continue;
+                    }
+                };
+                // Follow symbolic links
+                let metadata = tokio::fs::metadata(&path).await?;
+
+                if metadata.is_dir() {
+                    if !visited.insert(tokio::fs::canonicalize(&path).await?) {
+                        warn!("Skipping {}, directory was already visited", path.display());
+                        continue;
+                    }
+
+                    let created = client
+                        .create_folder(folder_id, &name)?
+                        .if_not_exists(true)
+                        .execute()
+                        .await?;
+                    let sub_folder_id = created
+                        .metadata
+                        .and_then(|m| m.folderid)
+                        .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+                    debug!("Created folder {} for {}", sub_folder_id, path.display());
+
+                    folders += 1;
+                    pending.push((path, sub_folder_id));
+                } else if metadata.is_file() {
+                    let relative = path
+                        .strip_prefix(&self.local_dir)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .to_string();
+                    files.push(LocalFile {
+                        path,
+                        relative,
+                        size: metadata.len(),
+                        folder_id,
+                    });
+                }
+            }
+        }
+
+        let files_total = files.len() as u64;
+        let bytes_total: u64 = files.iter().map(|f| f.size).sum();
+        let mut files_done: u64 = 0;
+        let mut bytes_done: u64 = 0;
+
+        let uploaded: Vec<Metadata> = stream::iter(files.into_iter().map(|file| {
+            let client = &client;
+            async move {
+                let mut uploaded = client
+                    .upload_file_into_folder(file.folder_id)?
+                    .with_local_file(&file.path)
+                    .await?
+                    .upload()
+                    .await?;
+                let metadata = uploaded
+                    .metadata
+                    .pop()
+                    .ok_or(PCloudResult::InternalUploadError)?;
+                Ok::<(LocalFile, Metadata), Box<dyn std::error::Error + Send + Sync>>((
+                    file, metadata,
+                ))
+            }
+        }))
+        .buffer_unordered(self.concurrency)
+        .map_ok(|(file, metadata)| {
+            files_done += 1;
+            bytes_done += file.size;
+            debug!(
+                "Uploaded {} ({}/{})",
+                file.relative, files_done, files_total
+            );
+
+            if let Some(tx) = &progress {
+                // Never block the upload, the next update contains the overall progress anyway
+                let _ = tx.try_send(DirUploadProgress {
+                    files_done,
+                    files_total,
+                    bytes_done,
+                    bytes_total,
+                    current: file.relative,
+                });
+            }
+            metadata
+        })
+        .try_collect()
+        .await?;
+
+        Ok(UploadedDirectory {
+            folder_id: root_id,
+            folders,
+            files: uploaded,
+        })
+    }
+}
+
+impl PCloudClient {
+    /// Uploads the contents of a local directory into the given folder. Sub directories are created as sub folders (existing folders are reused), symbolic links are followed but every directory is uploaded only once. The files are uploaded concurrently. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata) as target.
+    pub fn upload_directory<T: FolderDescriptor, P: AsRef<Path>>(
+        &self,
+        folder_like: T,
+        local_dir: P,
+    ) -> Result<DirectoryUploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        DirectoryUploadRequestBuilder::into_folder(self, folder_like, local_dir)
+    }
+}
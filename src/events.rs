@@ -1,15 +1,30 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::file_ops::{FileDescriptor, PCloudFile};
 use crate::pcloud_client::PCloudClient;
-use crate::pcloud_model::{self, Diff, WithPCloudResult};
-use crate::pcloud_model::{DiffEntry, FileHistory};
-use chrono::{DateTime, TimeZone};
+use crate::pcloud_model::{self, Diff};
+use crate::pcloud_model::{DiffEntry, DiffEvent, FileHistory, Metadata};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::{stream, Stream};
 use log::{debug, warn};
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// Upper limit of the backoff between reconnection attempts of the event stream
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Number of attempts to resolve the folder hierarchy while filtering events by folder
+const RESOLVE_ATTEMPTS: u32 = 4;
+
+/// Initial backoff between attempts to resolve the folder hierarchy, doubled after every failed attempt
+const RESOLVE_BACKOFF: Duration = Duration::from_secs(1);
 
 /// Consumes a Receiver of DiffEntries, applies the given predicate on each entry and passes all accepted entries to the returned Receiver
 pub fn filter_stream<P>(mut source: Receiver<DiffEntry>, filter: P) -> Receiver<DiffEntry>
@@ -33,29 +48,222 @@ where
     rx
 }
 
+/// Consumes a Receiver of DiffEntries, applies the given function on each entry and passes all results to the returned Receiver
+pub fn map_stream<T, F>(mut source: Receiver<DiffEntry>, f: F) -> Receiver<T>
+where
+    T: Send + 'static,
+    F: Fn(DiffEntry) -> T + Send + 'static,
+{
+    let channel_size = 128;
+    let (tx, rx) = mpsc::channel::<T>(channel_size);
+
+    tokio::spawn(async move {
+        while let Some(entry) = source.recv().await {
+            match tx.send(f(entry)).await {
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    rx
+}
+
+/// Consumes a Receiver of DiffEntries and passes only entries of the given event type to the returned Receiver
+pub fn filter_by_event(source: Receiver<DiffEntry>, event: DiffEvent) -> Receiver<DiffEntry> {
+    filter_stream(source, move |entry| entry.event == event)
+}
+
+/// Consumes a Receiver of DiffEntries and passes only entries targeting files / folders directly within the given folder to the returned Receiver. If recursive is set, entries within all sub folders are passed, too. The folder hierarchy is resolved (and cached) using the given client. If it can not be resolved (even after some retries), the returned Receiver is closed instead of silently dropping entries.
+pub fn filter_by_folder(
+    mut source: Receiver<DiffEntry>,
+    client: &PCloudClient,
+    folder_id: u64,
+    recursive: bool,
+) -> Receiver<DiffEntry> {
+    let channel_size = 128;
+    let (tx, rx) = mpsc::channel::<DiffEntry>(channel_size);
+    let mut ancestry = FolderAncestry::new(client);
+
+    tokio::spawn(async move {
+        while let Some(entry) = source.recv().await {
+            ancestry.learn(&entry);
+
+            let parent_folder_id = entry.metadata.as_ref().and_then(|m| m.parentfolderid);
+            let accepted = match parent_folder_id {
+                Some(parent) if recursive => {
+                    match ancestry.is_within_retrying(parent, folder_id).await {
+                        Ok(within) => within,
+                        Err(e) => {
+                            warn!(
+                                "Failed to resolve the parents of folder {}, stop filtering events: {}",
+                                parent, e
+                            );
+                            break;
+                        }
+                    }
+                }
+                Some(parent) => parent == folder_id,
+                None => false,
+            };
+
+            if accepted {
+                match tx.send(entry).await {
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+/// Resolves and caches the chain of parent folders of folders
+pub(crate) struct FolderAncestry {
+    /// Client to resolve unknown folders
+    client: PCloudClient,
+    /// Known folders and their parent folders
+    parents: HashMap<u64, Option<u64>>,
+}
+
+impl FolderAncestry {
+    /// Creates an empty FolderAncestry
+    pub(crate) fn new(client: &PCloudClient) -> FolderAncestry {
+        FolderAncestry {
+            client: client.clone(),
+            parents: HashMap::new(),
+        }
+    }
+
+    /// Remembers the parent folder of a folder targeted by the given event
+    pub(crate) fn learn(&mut self, entry: &DiffEntry) {
+        if let Some(metadata) = &entry.metadata {
+            if let (true, Some(folder_id)) = (metadata.isfolder, metadata.folderid) {
+                self.parents.insert(folder_id, metadata.parentfolderid);
+            }
+        }
+    }
+
+    /// Returns the parent of the given folder (None for the root folder and for deleted folders not learned from events). Unknown folders are fetched from pCloud, only successful lookups are cached.
+    async fn parent_of(
+        &mut self,
+        folder_id: u64,
+    ) -> Result<Option<u64>, Box<dyn std::error::Error + Send + Sync>> {
+        if folder_id == 0 {
+            // Root folder has no parent
+            return Ok(None);
+        }
+
+        if let Some(parent) = self.parents.get(&folder_id) {
+            return Ok(*parent);
+        }
+
+        let parent = match self
+            .client
+            .list_folder(folder_id)?
+            .nofiles(true)
+            .get()
+            .await
+        {
+            Ok(stat) => stat.metadata.and_then(|m| m.parentfolderid),
+            Err(e) => match e.downcast_ref::<pcloud_model::PCloudResult>() {
+                // Deleted folders can not be resolved anymore
+                Some(pcloud_model::PCloudResult::DirectoryDoesNotExist) => None,
+                _ => {
+                    debug!("Failed to resolve parent of folder {}: {}", folder_id, e);
+                    return Err(e);
+                }
+            },
+        };
+
+        self.parents.insert(folder_id, parent);
+        Ok(parent)
+    }
+
+    /// Checks if the given folder is the given ancestor folder or one of its sub folders
+    pub(crate) async fn is_within(
+        &mut self,
+        folder_id: u64,
+        ancestor_id: u64,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut visited = HashSet::new();
+        let mut current = Some(folder_id);
+
+        while let Some(id) = current {
+            if id == ancestor_id {
+                return Ok(true);
+            }
+            if !visited.insert(id) {
+                // Guard against cycles
+                return Ok(false);
+            }
+            current = self.parent_of(id).await?;
+        }
+
+        Ok(false)
+    }
+
+    /// Like is_within(), but retries failed lookups of the folder hierarchy (like timeouts or server errors) a few times, waiting (doubled with every attempt) in between
+    pub(crate) async fn is_within_retrying(
+        &mut self,
+        folder_id: u64,
+        ancestor_id: u64,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut failed_attempts = 0;
+        loop {
+            match self.is_within(folder_id, ancestor_id).await {
+                Ok(within) => return Ok(within),
+                Err(e) if failed_attempts + 1 < RESOLVE_ATTEMPTS => {
+                    let backoff = RESOLVE_BACKOFF * 2u32.pow(failed_attempts);
+                    failed_attempts += 1;
+                    warn!(
+                        "Failed to resolve the parents of folder {}: {}. Retrying in {:?}",
+                        folder_id, e, backoff
+                    );
+                    sleep(backoff).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
 pub struct GetFileHistoryRequestBuilder {
     /// Client to actually perform the request
     client: PCloudClient,
     /// fileid of a file that history is requested for
     file: PCloudFile,
+    /// only return events generated after that time
+    after: Option<DateTime<Utc>>,
 }
 
 impl GetFileHistoryRequestBuilder {
     /// Creates a GetFileHistoryRequestBuilder instance
-    pub fn create<'a, T: FileDescriptor>(
+    pub fn create<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<GetFileHistoryRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<GetFileHistoryRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let file = file_like.to_file()?;
 
         let result = GetFileHistoryRequestBuilder {
             client: client.clone(),
             file: file,
+            after: None,
         };
 
         Ok(result)
     }
 
+    /// only return events generated after that time. pCloud always returns the full history, so the events are filtered client-side.
+    pub fn after<Tz>(mut self, value: &DateTime<Tz>) -> GetFileHistoryRequestBuilder
+    where
+        Tz: TimeZone,
+    {
+        self.after = Some(value.with_timezone(&Utc));
+        self
+    }
+
     /// returns event history of a file identified by fileid. File might be a deleted one. The output format is the same as of diff method.
     pub async fn get(self) -> Result<FileHistory, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!("{}/getfilehistory", self.client.api_host);
@@ -66,19 +274,143 @@ impl GetFileHistoryRequestBuilder {
 
         r = r.query(&[("fileid", file_id)]);
 
-        r = self.client.add_token(r);
+        let mut result = self
+            .client
+            .execute_json::<pcloud_model::FileHistory>(r)
+            .await?;
 
-        let result = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileHistory>()
-            .await?
-            .assert_ok()?;
+        if let Some(after) = self.after {
+            result.entries.retain(|entry| entry.time > after);
+        }
 
         Ok(result)
     }
 }
 
+/// Net changes of files and folders within a period of time (see PCloudClient::changes_since())
+#[derive(Debug, Default)]
+pub struct ChangeSet {
+    /// Files and folders created within the period (with their latest metadata)
+    pub created: Vec<Metadata>,
+    /// Files and folders existing before, which were modified within the period
+    pub modified: Vec<Metadata>,
+    /// Files and folders existing before, which were deleted within the period
+    pub deleted: Vec<Metadata>,
+}
+
+/// Change of a single file or folder while aggregating a ChangeSet
+struct NetChange {
+    /// Was the file or folder created within the period?
+    created: bool,
+    /// Is the file or folder deleted at the end of the period?
+    deleted: bool,
+    /// Latest metadata of the file or folder
+    metadata: Metadata,
+}
+
+impl ChangeSet {
+    /// Aggregates the given events into the net changes per file / folder (the last event wins). Files and folders created and deleted within the period are dropped. Events not targeting files or folders (like shares or user info) are ignored.
+    pub fn from_events(entries: Vec<DiffEntry>) -> ChangeSet {
+        // Keeps the order of the first appearance
+        let mut order: Vec<(bool, u64)> = Vec::new();
+        let mut changes: HashMap<(bool, u64), NetChange> = HashMap::new();
+
+        for entry in entries {
+            let metadata = match entry.metadata {
+                Some(metadata) => metadata,
+                None => continue,
+            };
+            let key = match (metadata.isfolder, metadata.fileid, metadata.folderid) {
+                (false, Some(file_id), _) => (false, file_id),
+                (true, _, Some(folder_id)) => (true, folder_id),
+                _ => continue,
+            };
+            let (created, deleted) = match entry.event {
+                DiffEvent::CreateFile | DiffEvent::CreateFolder => (true, false),
+                DiffEvent::ModifyFile | DiffEvent::ModifyFolder => (false, false),
+                DiffEvent::DeleteFile | DiffEvent::DeleteFolder => (false, true),
+                _ => continue,
+            };
+
+            match changes.get_mut(&key) {
+                Some(change) => {
+                    change.created = change.created || created;
+                    change.deleted = deleted;
+                    change.metadata = metadata;
+                }
+                None => {
+                    order.push(key);
+                    changes.insert(
+                        key,
+                        NetChange {
+                            created,
+                            deleted,
+                            metadata,
+                        },
+                    );
+                }
+            }
+        }
+
+        let mut result = ChangeSet::default();
+        for key in order {
+            if let Some(change) = changes.remove(&key) {
+                match (change.created, change.deleted) {
+                    (true, true) => {}
+                    (true, false) => result.created.push(change.metadata),
+                    (false, false) => result.modified.push(change.metadata),
+                    (false, true) => result.deleted.push(change.metadata),
+                }
+            }
+        }
+        result
+    }
+
+    /// Are there any changes?
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Checkpoint of an event stream (see DiffRequestBuilder::stream_with_checkpoint()), which tracks the diff id of the last processed event. Can be cloned to commit from several places.
+#[derive(Clone)]
+pub struct EventCheckpoint {
+    /// Latest committed diff id
+    diff_id: Arc<watch::Sender<u64>>,
+}
+
+impl EventCheckpoint {
+    /// Creates a checkpoint starting at the given diff id
+    fn new(diff_id: u64) -> EventCheckpoint {
+        let (tx, _) = watch::channel(diff_id);
+        EventCheckpoint {
+            diff_id: Arc::new(tx),
+        }
+    }
+
+    /// Marks all events up to the given diff id (usually the one of the event just processed) as processed. Diff ids lower than the current checkpoint are ignored.
+    pub fn commit(&self, diff_id: u64) {
+        self.diff_id.send_if_modified(|current| {
+            if diff_id > *current {
+                *current = diff_id;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Diff id of the last processed event
+    pub fn current(&self) -> u64 {
+        *self.diff_id.borrow()
+    }
+
+    /// Returns a Receiver notified about every committed diff id, e.g. to persist them in a separate task
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.diff_id.subscribe()
+    }
+}
+
 pub struct DiffRequestBuilder {
     /// Client to actually perform the request
     client: PCloudClient,
@@ -94,6 +426,16 @@ pub struct DiffRequestBuilder {
     timeout: Option<Duration>,
     /// if provided, no more than limit entries will be returned
     limit: Option<u64>,
+    /// Maximum number of reconnection attempts of the event stream after transient connection errors
+    reconnect_attempts: u32,
+    /// Initial backoff between reconnection attempts, doubled after every failed attempt (up to MAX_RECONNECT_BACKOFF)
+    reconnect_backoff: Duration,
+    /// Comma separated list of feeds to subscribe for (e.g. `diff`, `notifications`)
+    subscribe_for: Option<String>,
+    /// If set, the event stream stops once the token is cancelled
+    cancellation: Option<CancellationToken>,
+    /// If set, only events within this folder (or its sub folders) are passed
+    scope_folder_id: Option<u64>,
 }
 
 #[allow(dead_code)]
@@ -107,6 +449,11 @@ impl DiffRequestBuilder {
             limit: None,
             timeout: None,
             client: client.clone(),
+            reconnect_attempts: 0,
+            reconnect_backoff: Duration::from_secs(1),
+            subscribe_for: None,
+            cancellation: None,
+            scope_folder_id: None,
         }
     }
 
@@ -131,7 +478,7 @@ impl DiffRequestBuilder {
         self
     }
 
-    /// if set, the connection will block until an event arrives. Works only with diffid
+    /// if set, the connection will block until an event arrives. pCloud supports this only with a diffid, without one get() obtains the current diffid with a non-blocking call first.
     pub fn block(mut self, value: bool) -> DiffRequestBuilder {
         self.block = value;
         self
@@ -149,6 +496,53 @@ impl DiffRequestBuilder {
         self
     }
 
+    /// Subscribe for the given feeds (comma separated, e.g. `diff,notifications`). If not set, only the diff feed is delivered.
+    pub fn subscribe(mut self, value: &str) -> DiffRequestBuilder {
+        self.subscribe_for = Some(value.to_string());
+        self
+    }
+
+    /// If set, the event stream reconnects up to max_attempts times after transient connection errors, waiting backoff (doubled with every failed attempt, but at most 5 minutes) in between. Pass u32::MAX to reconnect (practically) forever. Streaming resumes from the last received diff id. Without reconnects (default) the stream ends on the first connection error.
+    pub fn reconnect(mut self, max_attempts: u32, backoff: Duration) -> DiffRequestBuilder {
+        self.reconnect_attempts = max_attempts;
+        self.reconnect_backoff = backoff;
+        self
+    }
+
+    /// Stops the event stream as soon as the given token is cancelled, even while waiting for events. Without a token the stream only stops once the receiver is closed and the current (blocking) request returned.
+    pub fn cancel_on(mut self, token: CancellationToken) -> DiffRequestBuilder {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Only passes events targeting files / folders within the given folder or any of its sub folders (applies to stream() and collect_all()). pCloud only offers an account-wide event feed, so all events are still fetched and filtered on the client. The folder hierarchy is resolved (and cached) while streaming. Failed lookups are retried a few times, if they keep failing the stream ends (collect_all() returns the error).
+    pub fn scope_to_folder(mut self, folder_id: u64) -> DiffRequestBuilder {
+        self.scope_folder_id = Some(folder_id);
+        self
+    }
+
+    /// Checks if the given error is a transient error (connection problems, server errors), which is worth a reconnect
+    fn is_transient_error(e: &Box<dyn std::error::Error + Send + Sync>) -> bool {
+        if let Some(err) = e.downcast_ref::<reqwest::Error>() {
+            err.is_connect()
+                || err.is_request()
+                || err.is_body()
+                || err
+                    .status()
+                    .map(|status| status.is_server_error())
+                    .unwrap_or(false)
+        } else if let Some(err) = e.downcast_ref::<pcloud_model::PCloudResult>() {
+            matches!(
+                err,
+                pcloud_model::PCloudResult::InternalError
+                    | pcloud_model::PCloudResult::InternalUploadError
+                    | pcloud_model::PCloudResult::TooManyLogins
+            )
+        } else {
+            false
+        }
+    }
+
     /// Streams a single batch of DiffEntries to the given Sender and returns the last diff id received
     async fn stream_once(
         self,
@@ -189,9 +583,15 @@ impl DiffRequestBuilder {
         };
 
         let (tx, rx) = mpsc::channel::<DiffEntry>(channel_size);
+        let scope = self
+            .scope_folder_id
+            .map(|folder_id| (self.client.clone(), folder_id));
 
         tokio::spawn(async move {
+            // A token which is never cancelled, if none was given
+            let cancellation = self.cancellation.clone().unwrap_or_default();
             let mut next_diff_id = self.diff_id;
+            let mut failed_attempts = 0;
             while !tx.is_closed() {
                 let next = DiffRequestBuilder {
                     /// There seem to be collisions when setting both after and diff_id
@@ -206,20 +606,56 @@ impl DiffRequestBuilder {
                     last: self.last.clone(),
                     limit: self.limit.clone(),
                     timeout: self.timeout.clone(),
+                    reconnect_attempts: self.reconnect_attempts,
+                    reconnect_backoff: self.reconnect_backoff,
+                    subscribe_for: self.subscribe_for.clone(),
+                    cancellation: None,
+                    scope_folder_id: None,
+                };
+
+                let result = tokio::select! {
+                    _ = cancellation.cancelled() => {
+                        debug!("Event stream cancelled");
+                        break;
+                    }
+                    result = next.stream_once(&tx) => result,
                 };
 
-                match next.stream_once(&tx).await {
+                match result {
                     Ok(diff_id) => {
                         next_diff_id = diff_id;
+                        failed_attempts = 0;
                     }
                     Err(e) => {
                         if let Some(err) = e.downcast_ref::<reqwest::Error>() {
                             // Ignore timeout errors and try next time
-                            if !err.is_timeout() {
-                                warn!("Connection errors during receiving events: {}", err);
-                                break;
+                            if err.is_timeout() {
+                                continue;
+                            }
+                        }
+
+                        if failed_attempts < self.reconnect_attempts
+                            && DiffRequestBuilder::is_transient_error(&e)
+                        {
+                            let backoff = 2u32
+                                .checked_pow(failed_attempts)
+                                .and_then(|factor| self.reconnect_backoff.checked_mul(factor))
+                                .unwrap_or(MAX_RECONNECT_BACKOFF)
+                                .min(MAX_RECONNECT_BACKOFF);
+                            failed_attempts += 1;
+                            warn!(
+                                "Connection errors during receiving events: {}. Reconnecting in {:?} (attempt {} of {})",
+                                e, backoff, failed_attempts, self.reconnect_attempts
+                            );
+                            tokio::select! {
+                                _ = cancellation.cancelled() => {
+                                    debug!("Event stream cancelled");
+                                    break;
+                                }
+                                _ = sleep(backoff) => {}
                             }
                         } else {
+                            warn!("Connection errors during receiving events: {}", e);
                             break;
                         }
                     }
@@ -227,20 +663,124 @@ impl DiffRequestBuilder {
             }
         });
 
-        rx
+        match scope {
+            Some((client, folder_id)) => filter_by_folder(rx, &client, folder_id, true),
+            None => rx,
+        }
+    }
+
+    /// Streams the events like stream(), but as futures::Stream, so the events can be processed with the usual stream combinators (map, filter, take, ...). Dropping the stream stops the streaming.
+    pub fn event_stream(self) -> impl Stream<Item = DiffEntry> {
+        stream::unfold(self.stream(), |mut rx| async move {
+            rx.recv().await.map(|entry| (entry, rx))
+        })
+    }
+
+    /// Streams the events like stream(), but additionally returns a checkpoint of the stream. Commit the diff id of each event to the checkpoint once the event is processed, the checkpoint publishes the latest committed diff id to its subscribers. Persist this value and pass it to after_diff_id() to resume the stream after a restart without losing events, which were received but not processed yet.
+    pub fn stream_with_checkpoint(self) -> (Receiver<DiffEntry>, EventCheckpoint) {
+        let checkpoint = EventCheckpoint::new(self.diff_id.unwrap_or(0));
+        (self.stream(), checkpoint)
+    }
+
+    /// Fetches all events after the given diff id (use 0 for the complete history) by repeatedly calling the /diff endpoint (without blocking) until no more events are returned. Only limit and subscribe are taken from this builder.
+    pub async fn collect_all(
+        self,
+        from_diff_id: u64,
+    ) -> Result<Vec<DiffEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut result = Vec::new();
+        let mut next_diff_id = from_diff_id;
+        let mut ancestry = FolderAncestry::new(&self.client);
+
+        loop {
+            let next = DiffRequestBuilder {
+                diff_id: Some(next_diff_id),
+                after: None,
+                last: None,
+                block: false,
+                timeout: self.timeout,
+                limit: self.limit,
+                client: self.client.clone(),
+                reconnect_attempts: 0,
+                reconnect_backoff: self.reconnect_backoff,
+                subscribe_for: self.subscribe_for.clone(),
+                cancellation: None,
+                scope_folder_id: None,
+            };
+
+            let diffs = next.get().await?;
+            if diffs.entries.is_empty() {
+                break;
+            }
+
+            debug!(
+                "Collected {} events after diff id {}",
+                diffs.entries.len(),
+                next_diff_id
+            );
+            for entry in diffs
+                .entries
+                .into_iter()
+                .filter(|entry| entry.diffid > next_diff_id)
+            {
+                if let Some(folder_id) = self.scope_folder_id {
+                    ancestry.learn(&entry);
+                    let parent_folder_id = entry.metadata.as_ref().and_then(|m| m.parentfolderid);
+                    match parent_folder_id {
+                        Some(parent) if ancestry.is_within_retrying(parent, folder_id).await? => {}
+                        _ => continue,
+                    }
+                }
+                result.push(entry);
+            }
+
+            // Guard against endless loops, if the server does not advance the diff id
+            if diffs.diffid <= next_diff_id {
+                warn!(
+                    "Diff id did not advance beyond {}, stop collecting events",
+                    next_diff_id
+                );
+                break;
+            }
+            next_diff_id = diffs.diffid;
+        }
+
+        Ok(result)
     }
 
     /// Fetches the events. No matter you configure the limit, not all events could be fetched at once. Therefore one has to call repeatedly with the diffid of the last result set in the next call.
+    /// pCloud only blocks if a diffid is given. If block is set without diffid (e.g. only with after), a non-blocking call is made first: if it returns events, they are returned immediately, otherwise the call is repeated blocking from the diffid it returned.
     pub async fn get(self) -> Result<Diff, Box<dyn std::error::Error + Send + Sync>> {
+        if self.block && self.diff_id.is_none() {
+            let diff = self.fetch(None, self.after.clone(), false).await?;
+            if !diff.entries.is_empty() {
+                return Ok(diff);
+            }
+
+            debug!("No events yet, blocking from diff id {}", diff.diffid);
+            // There seem to be collisions when setting both after and diff_id
+            return self.fetch(Some(diff.diffid), None, true).await;
+        }
+
+        self.fetch(self.diff_id, self.after.clone(), self.block)
+            .await
+    }
+
+    /// Performs a single call of the /diff endpoint
+    async fn fetch(
+        &self,
+        diff_id: Option<u64>,
+        after: Option<String>,
+        block: bool,
+    ) -> Result<Diff, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!("{}/diff", self.client.api_host);
         let mut r = self.client.client.get(url);
 
-        if let Some(v) = self.diff_id {
+        if let Some(v) = diff_id {
             r = r.query(&[("diffid", v)]);
         }
 
         // There seem to be collisions when setting both after and diff_id
-        if let Some(v) = self.after {
+        if let Some(v) = after {
             r = r.query(&[("after", v)]);
         }
 
@@ -252,8 +792,12 @@ impl DiffRequestBuilder {
             r = r.query(&[("limit", v)]);
         }
 
+        if let Some(v) = &self.subscribe_for {
+            r = r.query(&[("subscribefor", v)]);
+        }
+
         // if set, the connection will block until an event arrives. Works only with diffid
-        if self.block && self.diff_id.is_some() {
+        if block && diff_id.is_some() {
             r = r.query(&[("block", "1")]);
         }
 
@@ -276,11 +820,40 @@ impl PCloudClient {
         DiffRequestBuilder::create(self)
     }
 
+    /// Returns the net changes of files and folders since the given time, aggregated from all events since then (see ChangeSet::from_events())
+    pub async fn changes_since<Tz>(
+        &self,
+        time: &DateTime<Tz>,
+    ) -> Result<ChangeSet, Box<dyn std::error::Error + Send + Sync>>
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        let first = self.get_events().after(time).get().await?;
+        let mut entries = first.entries;
+
+        // pCloud only returns a limited number of events at once, fetch the rest by diff id
+        if !entries.is_empty() {
+            let rest = self.get_events().collect_all(first.diffid).await?;
+            entries.extend(rest);
+        }
+
+        Ok(ChangeSet::from_events(entries))
+    }
+
+    /// Returns a request for the event history of a file, which can be restricted to events after a certain time. File might be a deleted one.
+    pub fn file_history<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<GetFileHistoryRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        GetFileHistoryRequestBuilder::create(self, file_like)
+    }
+
     /// returns event history of a file. File might be a deleted one.
-    pub async fn get_file_history<'a, T: FileDescriptor>(
+    pub async fn get_file_history<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<FileHistory, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileHistory, Box<dyn std::error::Error + Send + Sync>> {
         let result = GetFileHistoryRequestBuilder::create(self, file_like)?
             .get()
             .await?;
@@ -1,16 +1,27 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display, path::Path, time::Duration};
 
 use crate::{
-    folder_ops::FolderDescriptor,
+    folder_ops::{FolderDescriptor, PCloudFolder},
+    path_cache::PathKind,
     pcloud_client::PCloudClient,
     pcloud_model::{
-        self, FileOrFolderStat, Metadata, PCloudResult, PublicFileLink, RevisionList, UploadedFile,
-        WithPCloudResult,
+        self, AlreadyExistsError, DeleteResult, FileChecksums, FileOrFolderStat, Metadata,
+        PCloudResult, PublicFileLink, RevisionList, TrashClearResponse, UploadProgressResponse,
+        UploadedFile, WithPCloudResult,
     },
 };
 use chrono::{DateTime, TimeZone};
-use log::debug;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use log::{debug, warn};
 use reqwest::{Body, RequestBuilder, Response};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    sync::mpsc::{self, Receiver},
+    task::JoinHandle,
+    time::sleep,
+};
+use tokio_util::{io::ReaderStream, sync::CancellationToken};
+use uuid::Uuid;
 
 /// Generic description of a pCloud File. Either by its file id (preferred) or by its path. Optionally give tuple with id / path and file revision
 pub trait FileDescriptor {
@@ -141,12 +152,32 @@ pub struct PCloudFile {
     pub(crate) path: Option<String>,
     /// File revision
     pub(crate) revision: Option<u64>,
+    /// ID of the folder containing the file (together with name)
+    pub(crate) folder_id: Option<u64>,
+    /// Name of the file within the folder (together with folder_id)
+    pub(crate) name: Option<String>,
 }
 
 impl PCloudFile {
+    /// Describes the file with the given name within the folder with the given id. Such a descriptor is resolved by fetching the file metadata (see get_file_metadata()), so it can be used everywhere the file id is resolved first. All other operations require a file id or path.
+    pub fn in_folder(folder_id: u64, name: &str) -> PCloudFile {
+        PCloudFile {
+            file_id: None,
+            path: None,
+            revision: None,
+            folder_id: Some(folder_id),
+            name: Some(name.to_string()),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.file_id.is_none() && self.path.is_none()
     }
+
+    /// Is the file described by its parent folder id and its name?
+    pub fn is_in_folder(&self) -> bool {
+        self.folder_id.is_some() && self.name.is_some()
+    }
 }
 
 impl Display for PCloudFile {
@@ -163,6 +194,8 @@ impl Display for PCloudFile {
             } else {
                 write!(f, "{}", path)
             }
+        } else if let (Some(folder_id), Some(name)) = (self.folder_id, &self.name) {
+            write!(f, "{}/{}", folder_id, name)
         } else {
             write!(f, "[Empty pCloud file descriptor!]")
         }
@@ -176,6 +209,8 @@ impl From<&str> for PCloudFile {
             file_id: None,
             path: Some(value.to_string()),
             revision: None,
+            folder_id: None,
+            name: None,
         }
     }
 }
@@ -187,6 +222,8 @@ impl From<String> for PCloudFile {
             file_id: None,
             path: Some(value),
             revision: None,
+            folder_id: None,
+            name: None,
         }
     }
 }
@@ -198,6 +235,8 @@ impl From<u64> for PCloudFile {
             file_id: Some(value),
             path: None,
             revision: None,
+            folder_id: None,
+            name: None,
         }
     }
 }
@@ -209,6 +248,8 @@ impl From<&u64> for PCloudFile {
             file_id: Some(value.clone()),
             path: None,
             revision: None,
+            folder_id: None,
+            name: None,
         }
     }
 }
@@ -225,6 +266,8 @@ impl TryFrom<&Metadata> for PCloudFile {
                 file_id: value.fileid,
                 path: None,
                 revision: None,
+                folder_id: None,
+                name: None,
             })
         }
     }
@@ -243,6 +286,9 @@ impl TryFrom<&FileOrFolderStat> for PCloudFile {
     }
 }
 
+/// Default maximum size of files read into memory by read_file_bytes()
+const DEFAULT_MAX_READ_SIZE: u64 = 16 * 1024 * 1024;
+
 /// Some methods can work with trees - that is set of files and folders, where folders can have files and subfolders inside them and so on.
 /// see https://docs.pcloud.com/structures/tree.html
 pub struct Tree {
@@ -258,6 +304,25 @@ pub struct Tree {
     exclude_folder_ids: Vec<u64>,
     /// If set, defines fileids that are not to be included in the tree structure.
     exclude_file_ids: Vec<u64>,
+    /// Entries given by path, which are resolved to ids before the tree is used (see resolve())
+    pending: Vec<PendingTreeEntry>,
+}
+
+/// Number of paths resolved concurrently when resolving a Tree
+const TREE_RESOLVE_CONCURRENCY: usize = 4;
+
+/// Number of download links requested concurrently
+const DOWNLOAD_LINK_CONCURRENCY: usize = 8;
+
+/// Number of checksums requested concurrently after an upload
+const CHECKSUM_CONCURRENCY: usize = 8;
+
+/// Entry of a Tree given by path, which still needs to be resolved to an id
+enum PendingTreeEntry {
+    File(String),
+    ExcludedFile(String),
+    Folder(String),
+    ExcludedFolder(String),
 }
 
 /// Some methods can work with trees - that is set of files and folders, where folders can have files and subfolders inside them and so on.
@@ -270,6 +335,7 @@ impl Tree {
             file_ids: Vec::default(),
             exclude_folder_ids: Vec::default(),
             exclude_file_ids: Vec::default(),
+            pending: Vec::default(),
             client: client.clone(),
         }
     }
@@ -348,20 +414,20 @@ impl Tree {
     }
 
     /// If set, files with corresponding ids will appear in the root folder of the tree structure.
-    pub async fn with_file<'a, T: FileDescriptor>(
+    pub async fn with_file<T: FileDescriptor>(
         mut self,
         file_like: T,
-    ) -> Result<Self, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let (file_id, _) = self.client.get_file_id(file_like).await?;
         self.file_ids.push(file_id);
         Ok(self)
     }
 
     /// If set, defines fileids that are not to be included in the tree structure.
-    pub async fn without_file<'a, T: FileDescriptor>(
+    pub async fn without_file<T: FileDescriptor>(
         mut self,
         file_like: T,
-    ) -> Result<Self, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let (file_id, _) = self.client.get_file_id(file_like).await?;
 
         self.exclude_file_ids.push(file_id);
@@ -369,10 +435,10 @@ impl Tree {
     }
 
     /// If set, defines one or more folders that will appear as folders in the root folder.
-    pub async fn with_folder<'a, T: FolderDescriptor>(
+    pub async fn with_folder<T: FolderDescriptor>(
         mut self,
         folder_like: T,
-    ) -> Result<Self, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let folder_id = self.client.get_folder_id(folder_like).await?;
 
         self.folder_ids.push(folder_id);
@@ -380,10 +446,10 @@ impl Tree {
     }
 
     /// If set, folders with the given id will be removed from the tree structure. This is useful when you want to include a folder in the tree structure with some of it's subfolders excluded.
-    pub async fn without_folder<'a, T: FolderDescriptor>(
+    pub async fn without_folder<T: FolderDescriptor>(
         mut self,
         folder_like: T,
-    ) -> Result<Self, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let folder_id = self.client.get_folder_id(folder_like).await?;
 
         self.exclude_folder_ids.push(folder_id);
@@ -391,15 +457,128 @@ impl Tree {
     }
 
     /// If set, contents of the folder with the given id will appear as root elements of the tree. The folder itself does not appear as a part of the structure.
-    pub async fn with_content_of_folder<'a, T: FolderDescriptor>(
+    pub async fn with_content_of_folder<T: FolderDescriptor>(
         mut self,
         folder_like: T,
-    ) -> Result<Self, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let folder_id = self.client.get_folder_id(folder_like).await?;
 
         self.folder_id = Some(folder_id);
         Ok(self)
     }
+
+    /// Adds files by their already known ids. In contrast to with_file() no request is necessary, so this can be chained without error handling.
+    pub fn add_files<I: IntoIterator<Item = u64>>(mut self, file_ids: I) -> Self {
+        self.file_ids.extend(file_ids);
+        self
+    }
+
+    /// Excludes files by their already known ids. In contrast to without_file() no request is necessary, so this can be chained without error handling.
+    pub fn exclude_files<I: IntoIterator<Item = u64>>(mut self, file_ids: I) -> Self {
+        self.exclude_file_ids.extend(file_ids);
+        self
+    }
+
+    /// Adds folders by their already known ids. In contrast to with_folder() no request is necessary, so this can be chained without error handling.
+    pub fn add_folders<I: IntoIterator<Item = u64>>(mut self, folder_ids: I) -> Self {
+        self.folder_ids.extend(folder_ids);
+        self
+    }
+
+    /// Excludes folders by their already known ids. In contrast to without_folder() no request is necessary, so this can be chained without error handling.
+    pub fn exclude_folders<I: IntoIterator<Item = u64>>(mut self, folder_ids: I) -> Self {
+        self.exclude_folder_ids.extend(folder_ids);
+        self
+    }
+
+    /// Contents of the folder with the given id will appear as root elements of the tree. In contrast to with_content_of_folder() no request is necessary.
+    pub fn content_of_folder(mut self, folder_id: u64) -> Self {
+        self.folder_id = Some(folder_id);
+        self
+    }
+
+    /// Adds all entries of a folder listing: the files by their ids and the sub folders by their ids as a whole (pCloud adds their contents recursively), so there is no need to add every single descendant. Since the folders are added as a whole, client-side filters of the listing (like filter_category()) are only reflected on the top level. No request is necessary.
+    pub fn add_listing(mut self, listing: &FileOrFolderStat) -> Self {
+        if let Some(metadata) = &listing.metadata {
+            for entry in &metadata.contents {
+                match (entry.isfolder, entry.folderid, entry.fileid) {
+                    (true, Some(folder_id), _) => self.folder_ids.push(folder_id),
+                    (false, _, Some(file_id)) => self.file_ids.push(file_id),
+                    _ => {}
+                }
+            }
+        }
+        self
+    }
+
+    /// Adds a file by its path. The path is resolved to a file id by resolve(), which is called automatically by all requests using the tree.
+    pub fn add_file_path(mut self, path: &str) -> Self {
+        self.pending.push(PendingTreeEntry::File(path.to_string()));
+        self
+    }
+
+    /// Excludes a file by its path. The path is resolved to a file id by resolve(), which is called automatically by all requests using the tree.
+    pub fn exclude_file_path(mut self, path: &str) -> Self {
+        self.pending
+            .push(PendingTreeEntry::ExcludedFile(path.to_string()));
+        self
+    }
+
+    /// Adds a folder by its path. The path is resolved to a folder id by resolve(), which is called automatically by all requests using the tree.
+    pub fn add_folder_path(mut self, path: &str) -> Self {
+        self.pending
+            .push(PendingTreeEntry::Folder(path.to_string()));
+        self
+    }
+
+    /// Excludes a folder by its path. The path is resolved to a folder id by resolve(), which is called automatically by all requests using the tree.
+    pub fn exclude_folder_path(mut self, path: &str) -> Self {
+        self.pending
+            .push(PendingTreeEntry::ExcludedFolder(path.to_string()));
+        self
+    }
+
+    /// Resolves all entries added by path to their ids. The paths are resolved concurrently (with bounded concurrency). Fails if any of the paths does not exist.
+    pub async fn resolve(mut self) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if self.pending.is_empty() {
+            return Ok(self);
+        }
+
+        let client = self.client.clone();
+        let pending = std::mem::take(&mut self.pending);
+
+        let resolved: Vec<(PendingTreeEntry, u64)> =
+            stream::iter(pending.into_iter().map(|entry| {
+                let client = &client;
+                async move {
+                    let id = match &entry {
+                        PendingTreeEntry::File(path) | PendingTreeEntry::ExcludedFile(path) => {
+                            client.get_file_id(path.as_str()).await?.0
+                        }
+                        PendingTreeEntry::Folder(path) | PendingTreeEntry::ExcludedFolder(path) => {
+                            client.get_folder_id(path.as_str()).await?
+                        }
+                    };
+                    Ok::<(PendingTreeEntry, u64), Box<dyn std::error::Error + Send + Sync>>((
+                        entry, id,
+                    ))
+                }
+            }))
+            .buffered(TREE_RESOLVE_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        for (entry, id) in resolved {
+            match entry {
+                PendingTreeEntry::File(_) => self.file_ids.push(id),
+                PendingTreeEntry::ExcludedFile(_) => self.exclude_file_ids.push(id),
+                PendingTreeEntry::Folder(_) => self.folder_ids.push(id),
+                PendingTreeEntry::ExcludedFolder(_) => self.exclude_folder_ids.push(id),
+            }
+        }
+
+        Ok(self)
+    }
 }
 
 pub struct CopyFileRequestBuilder {
@@ -423,34 +602,68 @@ pub struct CopyFileRequestBuilder {
     ctime: Option<i64>,
     /// File revision to fetch
     revision_id: Option<u64>,
+    /// If set, mtime and ctime are taken from the source file (unless set explicitly)
+    preserve_timestamps: bool,
 }
 
 #[allow(dead_code)]
 impl CopyFileRequestBuilder {
-    pub(crate) fn copy_file<'a, S: FileDescriptor, T: FolderDescriptor>(
+    pub(crate) fn copy_file<S: FileDescriptor, T: FolderDescriptor>(
         client: &PCloudClient,
         file_like: S,
         target_folder_like: T,
-    ) -> Result<CopyFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CopyFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let source = file_like.to_file()?;
         let target = target_folder_like.to_folder()?;
 
-        if !source.is_empty() && !target.is_empty() {
-            Ok(CopyFileRequestBuilder {
-                from_path: source.path,
-                from_file_id: source.file_id,
-                to_path: target.path,
-                to_folder_id: target.folder_id,
-                client: client.clone(),
-                to_name: None,
-                overwrite: true,
-                mtime: None,
-                ctime: None,
-                revision_id: source.revision,
-            })
-        } else {
+        if source.is_empty() {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
         }
+
+        if target.is_empty() && !target.is_child() {
+            Err(pcloud_model::PCloudResult::ProvidedAtLeastToPathOrToFolderIdOrToName)?
+        }
+
+        Ok(CopyFileRequestBuilder {
+            from_path: source.path,
+            from_file_id: source.file_id,
+            to_path: target.path,
+            to_folder_id: target.folder_id.or(target.parent_folder_id),
+            client: client.clone(),
+            to_name: target.name,
+            overwrite: true,
+            mtime: None,
+            ctime: None,
+            revision_id: source.revision,
+            preserve_timestamps: false,
+        })
+    }
+
+    /// Brings the target into one of the two unambiguous shapes pCloud understands:
+    /// - copy into a folder keeping the name: a folder id without name or a folder path ending with `/`
+    /// - copy to a specific path and name: a folder id with name or a full file path (a folder path with a new name is joined to the full file path)
+    fn shape_target(&mut self) -> Result<(), PCloudResult> {
+        if let Some(name) = &self.to_name {
+            if name.is_empty() || name.contains('/') {
+                return Err(PCloudResult::InvalidFileOrFolderName);
+            }
+        }
+
+        match (self.to_folder_id, self.to_path.take(), self.to_name.take()) {
+            (Some(_), None, name) => {
+                self.to_name = name;
+            }
+            (None, Some(path), Some(name)) => {
+                self.to_path = Some(pcloud_model::join_path(&path, &name)?);
+            }
+            (None, Some(path), None) => {
+                self.to_path = Some(path);
+            }
+            _ => {
+                return Err(PCloudResult::ProvidedAtLeastToPathOrToFolderIdOrToName);
+            }
+        }
+        Ok(())
     }
 
     // If it is set (default true) and file with the specified name already exists, it will be overwritten
@@ -469,7 +682,7 @@ impl CopyFileRequestBuilder {
         self
     }
 
-    ///  if set, file created time is set. It's required to provide mtime to set ctime. Have to be unix time seconds.
+    ///  if set, file created time is set. pCloud requires mtime to set ctime, so if no mtime is set, ctime is used as mtime, too.
     pub fn ctime<Tz>(mut self, value: &DateTime<Tz>) -> CopyFileRequestBuilder
     where
         Tz: TimeZone,
@@ -479,6 +692,12 @@ impl CopyFileRequestBuilder {
         self
     }
 
+    /// If set, the source file is fetched before copying and its modified and created time are applied to the copy. Explicitly set mtime / ctime values take precedence.
+    pub fn preserve_timestamps(mut self, value: bool) -> CopyFileRequestBuilder {
+        self.preserve_timestamps = value;
+        self
+    }
+
     /// name of the destination file. If omitted, then the original filename is used
     pub fn with_new_name(mut self, value: &str) -> CopyFileRequestBuilder {
         self.to_name = Some(value.to_string());
@@ -493,8 +712,26 @@ impl CopyFileRequestBuilder {
 
     // Execute the copy operation
     pub async fn execute(
-        self,
+        mut self,
     ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        self.shape_target()?;
+
+        if self.preserve_timestamps && self.mtime.is_none() {
+            let source = PCloudFile {
+                file_id: self.from_file_id,
+                path: self.from_path.clone(),
+                revision: self.revision_id,
+                folder_id: None,
+                name: None,
+            };
+            if let Some(metadata) = self.client.get_file_metadata(source).await?.metadata {
+                self.mtime = Some(metadata.modified.timestamp());
+                if self.ctime.is_none() {
+                    self.ctime = Some(metadata.created.timestamp());
+                }
+            }
+        }
+
         let mut r = self
             .client
             .client
@@ -516,7 +753,8 @@ impl CopyFileRequestBuilder {
             r = r.query(&[("tofolderid", v)]);
         }
 
-        if let Some(v) = self.mtime {
+        // pCloud rejects ctime without mtime, so the created time is used as modified time, too
+        if let Some(v) = self.mtime.or(self.ctime) {
             r = r.query(&[("mtime", v)]);
         }
 
@@ -536,14 +774,10 @@ impl CopyFileRequestBuilder {
             r = r.query(&[("noover", "1")]);
         }
 
-        r = self.client.add_token(r);
-
-        let result = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        let result = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(result)
     }
 }
@@ -567,22 +801,22 @@ pub struct MoveFileRequestBuilder {
 
 #[allow(dead_code)]
 impl MoveFileRequestBuilder {
-    pub(crate) fn move_file<'a, S: FileDescriptor, T: FolderDescriptor>(
+    pub(crate) fn move_file<S: FileDescriptor, T: FolderDescriptor>(
         client: &PCloudClient,
         file_like: S,
         target_folder_like: T,
-    ) -> Result<MoveFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<MoveFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let source = file_like.to_file()?;
         let target = target_folder_like.to_folder()?;
 
-        if !source.is_empty() && !target.is_empty() {
+        if !source.is_empty() && (!target.is_empty() || target.is_child()) {
             Ok(MoveFileRequestBuilder {
                 from_path: source.path,
                 from_file_id: source.file_id,
                 to_path: target.path,
-                to_folder_id: target.folder_id,
+                to_folder_id: target.folder_id.or(target.parent_folder_id),
                 client: client.clone(),
-                to_name: None,
+                to_name: target.name,
                 revision_id: source.revision,
             })
         } else {
@@ -611,7 +845,7 @@ impl MoveFileRequestBuilder {
             .client
             .post(format!("{}/renamefile", self.client.api_host));
 
-        if let Some(v) = self.from_path {
+        if let Some(v) = &self.from_path {
             r = r.query(&[("path", v)]);
         }
 
@@ -619,7 +853,7 @@ impl MoveFileRequestBuilder {
             r = r.query(&[("fileid", v)]);
         }
 
-        if let Some(v) = self.to_path {
+        if let Some(v) = &self.to_path {
             r = r.query(&[("topath", v)]);
         }
 
@@ -627,7 +861,7 @@ impl MoveFileRequestBuilder {
             r = r.query(&[("tofolderid", v)]);
         }
 
-        if let Some(v) = self.to_name {
+        if let Some(v) = &self.to_name {
             r = r.query(&[("toname", v)]);
         }
 
@@ -635,14 +869,26 @@ impl MoveFileRequestBuilder {
             r = r.query(&[("revisionid", v)]);
         }
 
-        r = self.client.add_token(r);
+        if self.client.is_dry_run() {
+            self.client.log_dry_run(r);
+            return Ok(pcloud_model::FileOrFolderStat::dry_run());
+        }
 
-        let result = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        self.client
+            .invalidate_cached_file(self.from_file_id, self.from_path.as_deref());
+        // An existing file at the target gets replaced
+        if let Some(to_path) = &self.to_path {
+            self.client.invalidate_cached_file(None, Some(to_path));
+            if let Some(to_name) = &self.to_name {
+                let target = format!("{}/{}", to_path.trim_end_matches('/'), to_name);
+                self.client.invalidate_cached_file(None, Some(&target));
+            }
+        }
+
+        let result = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(result)
     }
 }
@@ -664,14 +910,26 @@ pub struct UploadRequestBuilder {
     ctime: Option<i64>,
     /// files to upload
     files: Vec<reqwest::multipart::Part>,
+    /// names of the files to upload (same order as files)
+    file_names: Vec<String>,
+    /// if set, the upload fails if a file with the same name already exists in the folder
+    fail_if_exists: bool,
+    /// if set, missing folders of the target path are created first
+    mkdirs: bool,
+    /// key to retrieve the progress of the upload
+    progress_hash: Option<String>,
+    /// if set, missing checksums of the uploaded files are fetched after the upload
+    request_checksums: bool,
+    /// expected SHA-1 checksums by file name, files already present with this checksum are not uploaded again
+    unchanged_sha1: HashMap<String, String>,
 }
 
 #[allow(dead_code)]
 impl UploadRequestBuilder {
-    pub(crate) fn into_folder<'a, T: FolderDescriptor>(
+    pub(crate) fn into_folder<T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like: T,
-    ) -> Result<UploadRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<UploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = folder_like.to_folder()?;
 
         if !f.is_empty() {
@@ -684,13 +942,19 @@ impl UploadRequestBuilder {
                 mtime: None,
                 ctime: None,
                 files: Vec::new(),
+                file_names: Vec::new(),
+                fail_if_exists: false,
+                mkdirs: false,
+                progress_hash: None,
+                request_checksums: false,
+                unchanged_sha1: HashMap::new(),
             })
         } else {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
         }
     }
 
-    ///  If is set, partially uploaded files will not be saved (defaults to true)
+    ///  If is set, partially uploaded files will not be saved (defaults to true). Streamed bodies of unknown length are discarded completely if the connection drops, set it to false to keep the partially uploaded file instead.
     pub fn no_partial(mut self, value: bool) -> UploadRequestBuilder {
         self.no_partial = value;
         self
@@ -702,6 +966,38 @@ impl UploadRequestBuilder {
         self
     }
 
+    /// If set, the folder is checked for files with the same names before uploading and the upload fails with an AlreadyExistsError (containing the metadata of the existing file) instead of overwriting or renaming it. Since pCloud has no such option, there is a small window for race conditions with other clients.
+    pub fn fail_if_exists(mut self, value: bool) -> UploadRequestBuilder {
+        self.fail_if_exists = value;
+        self
+    }
+
+    /// Key to monitor the upload with PCloudClient::upload_progress() while it is running. Must be unique for each upload.
+    pub fn with_progress_hash(mut self, value: &str) -> UploadRequestBuilder {
+        self.progress_hash = Some(value.to_string());
+        self
+    }
+
+    /// If set, the returned UploadedFile is guaranteed to contain the checksums of all uploaded files (in the same order as the file ids), so the integrity can be verified immediately. pCloud usually returns them with the upload already, only if they are missing they are fetched with one checksumfile call per file (at most 8 at once).
+    pub fn request_checksums(mut self, value: bool) -> UploadRequestBuilder {
+        self.request_checksums = value;
+        self
+    }
+
+    /// Skips the upload of the file added last, if a file with the same name and the given SHA-1 checksum (hex encoded) already exists in the target folder. The metadata of the existing file is returned instead, as if it was uploaded. If there is no such file yet (or its content differs), the file is uploaded as usual. Useful for idempotent backups, which should not upload unchanged files again.
+    pub fn skip_if_unchanged(mut self, sha1: &str) -> UploadRequestBuilder {
+        if let Some(name) = self.file_names.last() {
+            self.unchanged_sha1.insert(name.clone(), sha1.to_string());
+        }
+        self
+    }
+
+    /// If set, all missing folders of the target path are created before the upload (like `mkdir -p`). Has no effect if the target folder is given by id. Defaults to false.
+    pub fn mkdirs(mut self, value: bool) -> UploadRequestBuilder {
+        self.mkdirs = value;
+        self
+    }
+
     /// if set, file modified time is set. Have to be unix time seconds.
     pub fn mtime<Tz>(mut self, value: &DateTime<Tz>) -> UploadRequestBuilder
     where
@@ -712,7 +1008,7 @@ impl UploadRequestBuilder {
         self
     }
 
-    ///  if set, file created time is set. It's required to provide mtime to set ctime. Have to be unix time seconds.
+    ///  if set, file created time is set. pCloud requires mtime to set ctime, so if no mtime is set, ctime is used as mtime, too.
     pub fn ctime<Tz>(mut self, value: &DateTime<Tz>) -> UploadRequestBuilder
     where
         Tz: TimeZone,
@@ -726,11 +1022,179 @@ impl UploadRequestBuilder {
     pub fn with_file<T: Into<Body>>(mut self, file_name: &str, body: T) -> UploadRequestBuilder {
         let file_part = reqwest::multipart::Part::stream(body).file_name(file_name.to_string());
         self.files.push(file_part);
+        self.file_names.push(file_name.to_string());
         self
     }
 
+    /// Adds a file to the upload request, whose content is streamed from the given reader (like a decrypting or compressing layer on top of another source). The content length is unknown in advance, so the content is sent in chunks.
+    pub fn with_async_read<R: AsyncRead + Send + Sync + 'static>(
+        self,
+        file_name: &str,
+        reader: R,
+    ) -> UploadRequestBuilder {
+        self.with_file(file_name, Body::wrap_stream(ReaderStream::new(reader)))
+    }
+
+    /// Adds a file with the given MIME type (like `application/json`) to the upload request, so pCloud stores this content type instead of guessing it. Fails if the MIME type can not be parsed.
+    pub fn with_file_mime<T: Into<Body>>(
+        mut self,
+        file_name: &str,
+        body: T,
+        mime: &str,
+    ) -> Result<UploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let file_part = reqwest::multipart::Part::stream(body)
+            .file_name(file_name.to_string())
+            .mime_str(mime)?;
+        self.files.push(file_part);
+        self.file_names.push(file_name.to_string());
+        Ok(self)
+    }
+
+    /// Adds a local file to the upload request. The file name is taken from the path and the content is streamed from disk. Fails if the file can not be opened.
+    pub async fn with_local_file<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<UploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(PCloudResult::InvalidFileOrFolderName)?
+            .to_string();
+
+        debug!("Adding local file {} to upload", path.display());
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+
+        let file_part = reqwest::multipart::Part::stream_with_length(Body::from(file), length)
+            .file_name(file_name.clone());
+        self.files.push(file_part);
+        self.file_names.push(file_name);
+        Ok(self)
+    }
+
+    /// Checks that none of the files to upload already exists in the target folder
+    async fn assert_not_existing(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let folder = PCloudFolder {
+            folder_id: self.folder_id,
+            path: self.path.clone(),
+            parent_folder_id: None,
+            name: None,
+        };
+        let listing = self.client.list_folder(folder)?.get().await?;
+
+        let existing = listing
+            .metadata
+            .map(|m| m.contents)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|m| self.file_names.contains(&m.name));
+
+        match existing {
+            Some(metadata) => {
+                debug!("File {} already exists, upload aborted", metadata.name);
+                Err(AlreadyExistsError { metadata })?
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Looks up the files of skip_if_unchanged() in the target folder and returns the metadata and checksums of those, which are already present with the expected checksum (by file name)
+    async fn find_unchanged(
+        &self,
+    ) -> Result<HashMap<String, (Metadata, FileChecksums)>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let mut unchanged = HashMap::new();
+        if self.unchanged_sha1.is_empty() {
+            return Ok(unchanged);
+        }
+
+        let folder = PCloudFolder {
+            folder_id: self.folder_id,
+            path: self.path.clone(),
+            parent_folder_id: None,
+            name: None,
+        };
+        let listing = self.client.list_folder(folder)?.get().await?;
+
+        for existing in listing.metadata.map(|m| m.contents).unwrap_or_default() {
+            let expected = match (existing.isfolder, self.unchanged_sha1.get(&existing.name)) {
+                (false, Some(expected)) => expected,
+                // Not uploaded yet or a folder with the same name
+                _ => continue,
+            };
+            let file_id = existing.fileid.ok_or(PCloudResult::FileNotFound)?;
+
+            let checksums = self.client.checksum_file(file_id)?.get().await?;
+            if checksums
+                .sha1
+                .as_deref()
+                .map(|sha1| sha1.eq_ignore_ascii_case(expected))
+                .unwrap_or(false)
+            {
+                debug!("File {} is unchanged, upload skipped", existing.name);
+                unchanged.insert(existing.name.clone(), (existing, checksums));
+            }
+        }
+
+        Ok(unchanged)
+    }
+
+    /// Starts the upload in the background and polls the server-side progress in the given interval (using a progress hash). The notification stops once the upload is finished or the receiver is closed. The handle returns the result once the upload is finished.
+    pub fn upload_with_progress_notification(
+        mut self,
+        polling_interval: Duration,
+    ) -> (
+        JoinHandle<Result<UploadedFile, Box<dyn std::error::Error + Send + Sync>>>,
+        Receiver<UploadProgressResponse>,
+    ) {
+        let progress_hash = self
+            .progress_hash
+            .get_or_insert_with(|| Uuid::new_v4().to_string())
+            .clone();
+        let progress_client = self.client.clone();
+        let finished = CancellationToken::new();
+        let finished_upload = finished.clone();
+
+        let (tx, rx) = mpsc::channel::<UploadProgressResponse>(32);
+
+        tokio::spawn(async move {
+            while !tx.is_closed() {
+                // Wait first, the upload needs to be started before its progress is known
+                tokio::select! {
+                    _ = finished.cancelled() => {
+                        debug!("Upload finished, progress notification stopped");
+                        break;
+                    }
+                    _ = sleep(polling_interval) => {}
+                }
+                match progress_client.upload_progress(&progress_hash).await {
+                    Ok(progress) => {
+                        let complete = progress.complete;
+                        if tx.send(progress).await.is_err() || complete {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Errors during receiving uploadprogress: {}", err);
+                    }
+                }
+            }
+        });
+
+        let handle = tokio::spawn(async move {
+            let result = self.upload().await;
+            finished_upload.cancel();
+            result
+        });
+
+        (handle, rx)
+    }
+
     // Finally uploads the files
-    pub async fn upload(self) -> Result<UploadedFile, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn upload(
+        mut self,
+    ) -> Result<UploadedFile, Box<dyn std::error::Error + Send + Sync>> {
         if self.files.is_empty() {
             // Short cut operation if no files are configured to upload
             debug!("Requested file upload, but no files are added to the request.");
@@ -738,16 +1202,110 @@ impl UploadRequestBuilder {
                 result: PCloudResult::Ok,
                 fileids: Vec::default(),
                 metadata: Vec::default(),
+                checksums: Vec::default(),
             };
             return Ok(result);
         }
 
+        if self.mkdirs && self.folder_id.is_none() {
+            if let Some(p) = self.path.take() {
+                let (folder_id, _) = self.client.create_folders_recursive(&p).await?;
+                self.folder_id = Some(folder_id);
+            }
+        }
+
+        // Unchanged files are removed from the upload and merged into the result afterwards
+        let mut unchanged = self.find_unchanged().await?;
+        let file_names = self.file_names.clone();
+        if !unchanged.is_empty() {
+            let files = std::mem::take(&mut self.files);
+            let names = std::mem::take(&mut self.file_names);
+            for (part, name) in files.into_iter().zip(names) {
+                if !unchanged.contains_key(&name) {
+                    self.files.push(part);
+                    self.file_names.push(name);
+                }
+            }
+        }
+
+        if self.fail_if_exists {
+            self.assert_not_existing().await?;
+        }
+
+        let mut result = if self.files.is_empty() {
+            debug!("All files are unchanged, nothing to upload");
+            UploadedFile {
+                result: PCloudResult::Ok,
+                fileids: Vec::default(),
+                metadata: Vec::default(),
+                checksums: Vec::default(),
+            }
+        } else {
+            self.send().await?
+        };
+
+        if !unchanged.is_empty() {
+            // The checksums of the unchanged files are known, so they are only merged if the uploaded ones are complete, too
+            let complete_checksums = result.checksums.len() == result.fileids.len();
+            let mut fileids = result.fileids.into_iter();
+            let mut metadata = result.metadata.into_iter();
+            let mut checksums = result.checksums.into_iter();
+            let mut merged = UploadedFile {
+                result: result.result,
+                fileids: Vec::new(),
+                metadata: Vec::new(),
+                checksums: Vec::new(),
+            };
+
+            for name in file_names {
+                if let Some((existing, existing_checksums)) = unchanged.remove(&name) {
+                    merged
+                        .fileids
+                        .push(existing.fileid.ok_or(PCloudResult::FileNotFound)?);
+                    merged.metadata.push(existing);
+                    merged.checksums.push(existing_checksums);
+                } else {
+                    merged.fileids.extend(fileids.next());
+                    merged.metadata.extend(metadata.next());
+                    merged.checksums.extend(checksums.next());
+                }
+            }
+
+            if !complete_checksums {
+                merged.checksums.clear();
+            }
+            result = merged;
+        }
+
+        if self.request_checksums && result.checksums.len() != result.fileids.len() {
+            debug!(
+                "Upload returned {} checksums for {} files, fetching them",
+                result.checksums.len(),
+                result.fileids.len()
+            );
+            let client = &self.client;
+            result.checksums = stream::iter(
+                result
+                    .fileids
+                    .iter()
+                    .map(|file_id| async move { client.checksum_file(*file_id)?.get().await }),
+            )
+            .buffered(CHECKSUM_CONCURRENCY)
+            .try_collect()
+            .await?;
+        }
+
+        Ok(result)
+    }
+
+    /// Sends the upload request with all files
+    async fn send(&mut self) -> Result<UploadedFile, Box<dyn std::error::Error + Send + Sync>> {
         let mut r = self
             .client
             .client
             .post(format!("{}/uploadfile", self.client.api_host));
 
-        if let Some(v) = self.path {
+        if let Some(v) = &self.path {
             r = r.query(&[("path", v)]);
         }
 
@@ -763,7 +1321,8 @@ impl UploadRequestBuilder {
             r = r.query(&[("renameifexists", "1")]);
         }
 
-        if let Some(v) = self.mtime {
+        // pCloud rejects ctime without mtime, so the created time is used as modified time, too
+        if let Some(v) = self.mtime.or(self.ctime) {
             r = r.query(&[("mtime", v)]);
         }
 
@@ -771,16 +1330,18 @@ impl UploadRequestBuilder {
             r = r.query(&[("ctime", v)]);
         }
 
-        r = self.client.add_token(r);
+        if let Some(v) = &self.progress_hash {
+            r = r.query(&[("progresshash", v)]);
+        }
 
         let mut form = reqwest::multipart::Form::new();
-        for part in self.files {
+        for part in std::mem::take(&mut self.files) {
             form = form.part("part", part);
         }
 
         r = r.multipart(form);
 
-        let result = r.send().await?.json::<UploadedFile>().await?.assert_ok()?;
+        let result = self.client.execute_json::<UploadedFile>(r).await?;
         Ok(result)
     }
 }
@@ -804,10 +1365,10 @@ pub struct PublicFileLinkRequestBuilder {
 
 #[allow(dead_code)]
 impl PublicFileLinkRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+    pub(crate) fn for_file<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<PublicFileLinkRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<PublicFileLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f: PCloudFile = file_like.to_file()?;
 
         if !f.is_empty() {
@@ -907,19 +1468,15 @@ impl PublicFileLinkRequestBuilder {
             r = r.query(&[("revisionid", v)]);
         }
 
-        r = self.client.add_token(r);
-
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::PublicFileLink>()
-            .await?
-            .assert_ok()?;
+        let diff = self
+            .client
+            .execute_json::<pcloud_model::PublicFileLink>(r)
+            .await?;
         Ok(diff)
     }
 }
 
-pub(crate) struct PublicFileDownloadRequestBuilder {
+pub struct PublicFileDownloadRequestBuilder {
     /// Client to actually perform the request
     client: PCloudClient,
     /// either 'code' or 'shortcode'
@@ -970,86 +1527,356 @@ impl PublicFileDownloadRequestBuilder {
             r = r.query(&[("fileid", id)]);
         }
 
-        r = self.client.add_token(r);
-
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::DownloadLink>()
-            .await?
-            .assert_ok()?;
+        let diff = self
+            .client
+            .execute_json::<pcloud_model::DownloadLink>(r)
+            .await?;
         Ok(diff)
     }
 }
 
-pub struct ListRevisionsRequestBuilder {
+pub struct PublicThumbnailRequestBuilder {
     /// Client to actually perform the request
     client: PCloudClient,
-    ///  ID of the  file
+    /// either 'code' or 'shortcode'
+    code: String,
+    ///  File id, if the link is to a folder
     file_id: Option<u64>,
-    /// Path to the  file
-    path: Option<String>,
+    /// Size of the thumbnail as WIDTHxHEIGHT
+    size: String,
+    /// If set, the thumbnail is cropped to exactly the requested size
+    crop: bool,
+    /// Format of the thumbnail (png or jpeg)
+    thumb_type: Option<String>,
 }
 
-impl ListRevisionsRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+#[allow(dead_code)]
+impl PublicThumbnailRequestBuilder {
+    /// Requests the thumbnail of a public file (or a file within a public folder) with the given code
+    pub(crate) fn for_public_file(
         client: &PCloudClient,
-        file_like: T,
-    ) -> Result<ListRevisionsRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
-        let f = file_like.to_file()?;
-
-        if !f.is_empty() {
-            Ok(ListRevisionsRequestBuilder {
-                file_id: f.file_id,
-                path: f.path,
-                client: client.clone(),
-            })
-        } else {
-            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        code: &str,
+        file_id: Option<u64>,
+        size: &str,
+    ) -> PublicThumbnailRequestBuilder {
+        PublicThumbnailRequestBuilder {
+            code: code.to_string(),
+            file_id,
+            size: size.to_string(),
+            crop: false,
+            thumb_type: None,
+            client: client.clone(),
         }
     }
 
-    /// Executes the request
-    pub async fn get(self) -> Result<RevisionList, Box<dyn std::error::Error + Send + Sync>> {
+    /// If set, the thumbnail is cropped to exactly the requested size. Otherwise the aspect ratio of the image is kept and the thumbnail might be smaller in one dimension.
+    pub fn crop(mut self, value: bool) -> PublicThumbnailRequestBuilder {
+        self.crop = value;
+        self
+    }
+
+    /// Format of the thumbnail, either png or jpeg (default)
+    pub fn with_type(mut self, value: &str) -> PublicThumbnailRequestBuilder {
+        self.thumb_type = Some(value.to_string());
+        self
+    }
+
+    /// Create the link to the thumbnail
+    pub async fn get(
+        self,
+    ) -> Result<pcloud_model::DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
         let mut r = self
             .client
             .client
-            .get(format!("{}/listrevisions", self.client.api_host));
+            .get(format!("{}/getpubthumblink", self.client.api_host));
+
+        r = r.query(&[("code", self.code)]);
+        r = r.query(&[("size", self.size)]);
 
         if let Some(id) = self.file_id {
-            debug!("Requesting file revisions for file {}", id);
             r = r.query(&[("fileid", id)]);
         }
 
-        if let Some(p) = self.path {
-            debug!("Requesting file revisions for file {}", p);
-            r = r.query(&[("path", p)]);
+        if self.crop {
+            r = r.query(&[("crop", "1")]);
+        }
+
+        if let Some(v) = self.thumb_type {
+            r = r.query(&[("type", v)]);
         }
 
-        r = self.client.add_token(r);
+        let link = self
+            .client
+            .execute_json::<pcloud_model::DownloadLink>(r)
+            .await?;
+        Ok(link)
+    }
 
-        let result = r.send().await?.json::<RevisionList>().await?.assert_ok()?;
-        Ok(result)
+    /// Downloads the thumbnail
+    pub async fn download(self) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        let client = self.client.clone();
+        let link = self.get().await?;
+        client.download_link(&link).await
     }
 }
 
-pub struct ChecksumFileRequestBuilder {
+pub struct SavePublicFileRequestBuilder {
     /// Client to actually perform the request
     client: PCloudClient,
-    ///  ID of the  file
+    /// either 'code' or 'shortcode'
+    code: String,
+    ///  File id, if the link is to a folder
     file_id: Option<u64>,
-    /// Path to the  file
-    path: Option<String>,
-    /// File revision to fetch
-    revision_id: Option<u64>,
-}
-
-#[allow(dead_code)]
+    /// destination folder path
+    to_path: Option<String>,
+    /// destination folder id
+    to_folder_id: Option<u64>,
+    /// New file name
+    to_name: Option<String>,
+}
+
+#[allow(dead_code)]
+impl SavePublicFileRequestBuilder {
+    /// Copies the public file with the given code into the given folder of the user
+    pub(crate) fn save_to<T: FolderDescriptor>(
+        client: &PCloudClient,
+        code: &str,
+        target_folder_like: T,
+    ) -> Result<SavePublicFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let target = target_folder_like.to_folder()?;
+
+        if !target.is_empty() || target.is_child() {
+            Ok(SavePublicFileRequestBuilder {
+                code: code.to_string(),
+                file_id: None,
+                to_path: target.path,
+                to_folder_id: target.folder_id.or(target.parent_folder_id),
+                to_name: target.name,
+                client: client.clone(),
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// Id of the file to copy, if the public link is to a folder
+    pub fn with_file_id(mut self, value: u64) -> SavePublicFileRequestBuilder {
+        self.file_id = Some(value);
+        self
+    }
+
+    /// name of the destination file. If omitted, then the original filename is used
+    pub fn with_new_name(mut self, value: &str) -> SavePublicFileRequestBuilder {
+        self.to_name = Some(value.to_string());
+        self
+    }
+
+    /// Execute the copy operation (server-side, the file is not downloaded)
+    pub async fn execute(
+        self,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/copypubfile", self.client.api_host));
+
+        r = r.query(&[("code", self.code)]);
+
+        if let Some(id) = self.file_id {
+            r = r.query(&[("fileid", id)]);
+        }
+
+        if let Some(v) = self.to_path {
+            r = r.query(&[("topath", v)]);
+        }
+
+        if let Some(v) = self.to_folder_id {
+            r = r.query(&[("tofolderid", v)]);
+        }
+
+        if let Some(v) = self.to_name {
+            r = r.query(&[("toname", v)]);
+        }
+
+        let result = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
+        Ok(result)
+    }
+}
+
+pub struct ShowPublicLinkRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    /// either 'code' or 'shortcode'
+    code: String,
+    /// Password of the link, if it is password protected
+    link_password: Option<String>,
+}
+
+#[allow(dead_code)]
+impl ShowPublicLinkRequestBuilder {
+    /// Requests the metadata of a public link with a given code
+    pub(crate) fn for_public_link(
+        client: &PCloudClient,
+        code: &str,
+    ) -> ShowPublicLinkRequestBuilder {
+        ShowPublicLinkRequestBuilder {
+            code: code.to_string(),
+            link_password: None,
+            client: client.clone(),
+        }
+    }
+
+    /// Password of the link, if it is password protected
+    pub fn with_password(mut self, value: &str) -> ShowPublicLinkRequestBuilder {
+        self.link_password = Some(value.to_string());
+        self
+    }
+
+    /// Fetches the metadata of the public link. For folders the metadata contains the full folder tree.
+    pub async fn get(self) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/showpublink", self.client.api_host));
+
+        debug!("Requesting public link {}", self.code);
+        r = r.query(&[("code", self.code)]);
+
+        if let Some(v) = self.link_password {
+            r = r.query(&[("linkpassword", v)]);
+        }
+
+        let result = self.client.execute_json::<FileOrFolderStat>(r).await?;
+        Ok(result)
+    }
+}
+
+pub struct ListRevisionsRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    ///  ID of the  file
+    file_id: Option<u64>,
+    /// Path to the  file
+    path: Option<String>,
+}
+
+impl ListRevisionsRequestBuilder {
+    pub(crate) fn for_file<T: FileDescriptor>(
+        client: &PCloudClient,
+        file_like: T,
+    ) -> Result<ListRevisionsRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let f = file_like.to_file()?;
+
+        if !f.is_empty() {
+            Ok(ListRevisionsRequestBuilder {
+                file_id: f.file_id,
+                path: f.path,
+                client: client.clone(),
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// Executes the request
+    pub async fn get(self) -> Result<RevisionList, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/listrevisions", self.client.api_host));
+
+        if let Some(id) = self.file_id {
+            debug!("Requesting file revisions for file {}", id);
+            r = r.query(&[("fileid", id)]);
+        }
+
+        if let Some(p) = self.path {
+            debug!("Requesting file revisions for file {}", p);
+            r = r.query(&[("path", p)]);
+        }
+
+        let result = self.client.execute_json::<RevisionList>(r).await?;
+        Ok(result)
+    }
+}
+
+pub struct RevertRevisionRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    ///  ID of the  file
+    file_id: Option<u64>,
+    /// Path to the  file
+    path: Option<String>,
+    /// Revision to revert to
+    revision_id: u64,
+}
+
+impl RevertRevisionRequestBuilder {
+    pub(crate) fn for_file<T: FileDescriptor>(
+        client: &PCloudClient,
+        file_like: T,
+        revision_id: u64,
+    ) -> Result<RevertRevisionRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let f = file_like.to_file()?;
+
+        if !f.is_empty() {
+            Ok(RevertRevisionRequestBuilder {
+                file_id: f.file_id,
+                path: f.path,
+                client: client.clone(),
+                revision_id,
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// Executes the request
+    pub async fn execute(
+        self,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/revertrevision", self.client.api_host));
+
+        if let Some(id) = self.file_id {
+            debug!("Reverting file {} to revision {}", id, self.revision_id);
+            r = r.query(&[("fileid", id)]);
+        }
+
+        if let Some(p) = self.path {
+            debug!("Reverting file {} to revision {}", p, self.revision_id);
+            r = r.query(&[("path", p)]);
+        }
+
+        r = r.query(&[("revisionid", self.revision_id)]);
+
+        let result = self.client.execute_json::<FileOrFolderStat>(r).await?;
+        Ok(result)
+    }
+}
+
+pub struct ChecksumFileRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    ///  ID of the  file
+    file_id: Option<u64>,
+    /// Path to the  file
+    path: Option<String>,
+    /// File revision to fetch
+    revision_id: Option<u64>,
+}
+
+#[allow(dead_code)]
 impl ChecksumFileRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+    pub(crate) fn for_file<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<ChecksumFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<ChecksumFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = file_like.to_file()?;
 
         if !f.is_empty() {
@@ -1093,14 +1920,10 @@ impl ChecksumFileRequestBuilder {
             r = r.query(&[("revisionid", v)]);
         }
 
-        r = self.client.add_token(r);
-
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileChecksums>()
-            .await?
-            .assert_ok()?;
+        let diff = self
+            .client
+            .execute_json::<pcloud_model::FileChecksums>(r)
+            .await?;
         Ok(diff)
     }
 }
@@ -1116,10 +1939,10 @@ pub struct FileDeleteRequestBuilder {
 
 #[allow(dead_code)]
 impl FileDeleteRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+    pub(crate) fn for_file<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<FileDeleteRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileDeleteRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = file_like.to_file()?;
 
         if !f.is_empty() {
@@ -1146,19 +1969,23 @@ impl FileDeleteRequestBuilder {
             r = r.query(&[("fileid", id)]);
         }
 
-        if let Some(p) = self.path {
+        if let Some(p) = &self.path {
             debug!("Requesting delete for file {}", p);
             r = r.query(&[("path", p)]);
         }
 
-        r = self.client.add_token(r);
+        if self.client.is_dry_run() {
+            self.client.log_dry_run(r);
+            return Ok(pcloud_model::FileOrFolderStat::dry_run());
+        }
 
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        self.client
+            .invalidate_cached_file(self.file_id, self.path.as_deref());
+
+        let diff = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(diff)
     }
 }
@@ -1176,10 +2003,10 @@ pub struct FileDownloadRequestBuilder {
 
 #[allow(dead_code)]
 impl FileDownloadRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+    pub(crate) fn for_file<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<FileDownloadRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileDownloadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = file_like.to_file()?;
 
         if !f.is_empty() {
@@ -1204,6 +2031,14 @@ impl FileDownloadRequestBuilder {
     pub async fn get(
         self,
     ) -> Result<pcloud_model::DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
+        let source = PCloudFile {
+            file_id: self.file_id,
+            path: self.path.clone(),
+            revision: self.revision_id,
+            folder_id: None,
+            name: None,
+        };
+
         let mut r = self
             .client
             .client
@@ -1223,15 +2058,12 @@ impl FileDownloadRequestBuilder {
             r = r.query(&[("revisionid", v)]);
         }
 
-        r = self.client.add_token(r);
-
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::DownloadLink>()
-            .await?
-            .assert_ok()?;
-        Ok(diff)
+        let mut link = self
+            .client
+            .execute_json::<pcloud_model::DownloadLink>(r)
+            .await?;
+        link.source = Some(source);
+        Ok(link)
     }
 }
 
@@ -1244,22 +2076,31 @@ pub struct FileStatRequestBuilder {
     path: Option<String>,
     /// File revision to fetch
     revision_id: Option<u64>,
+    /// ID of the folder containing the file (together with name)
+    folder_id: Option<u64>,
+    /// Name of the file within the folder (together with folder_id)
+    name: Option<String>,
+    /// If set, deleted files that can be undeleted are returned, too
+    show_deleted: bool,
 }
 
 #[allow(dead_code)]
 impl FileStatRequestBuilder {
-    pub(crate) fn for_file<'a, T: FileDescriptor>(
+    pub(crate) fn for_file<T: FileDescriptor>(
         client: &PCloudClient,
         file_like: T,
-    ) -> Result<FileStatRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileStatRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = file_like.to_file()?;
 
-        if !f.is_empty() {
+        if !f.is_empty() || f.is_in_folder() {
             Ok(FileStatRequestBuilder {
                 file_id: f.file_id,
                 path: f.path,
                 client: client.clone(),
                 revision_id: f.revision,
+                folder_id: f.folder_id,
+                name: f.name,
+                show_deleted: false,
             })
         } else {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
@@ -1272,16 +2113,38 @@ impl FileStatRequestBuilder {
         self
     }
 
+    /// If set, the metadata of deleted files (which can still be undeleted from the trash) is returned, too. Such files have isdeleted set.
+    pub fn show_deleted(mut self, value: bool) -> FileStatRequestBuilder {
+        self.show_deleted = value;
+        self
+    }
+
     /// Fetch the file metadata
     pub async fn get(
         self,
     ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut file_id = self.file_id;
+
+        if file_id.is_none() && self.path.is_none() {
+            if let (Some(folder_id), Some(name)) = (self.folder_id, self.name.as_ref()) {
+                // pCloud does not support stat by folder id and name, so look up the file in the folder
+                let metadata = self.find_in_folder(folder_id, name).await?;
+                if self.revision_id.is_none() {
+                    return Ok(FileOrFolderStat {
+                        result: PCloudResult::Ok,
+                        metadata: Some(metadata),
+                    });
+                }
+                file_id = metadata.fileid;
+            }
+        }
+
         let mut r = self
             .client
             .client
             .get(format!("{}/stat", self.client.api_host));
 
-        if let Some(id) = self.file_id {
+        if let Some(id) = file_id {
             debug!("Requesting file metadata for file {}", id);
             r = r.query(&[("fileid", id)]);
         }
@@ -1295,16 +2158,46 @@ impl FileStatRequestBuilder {
             r = r.query(&[("revisionid", v)]);
         }
 
-        r = self.client.add_token(r);
+        if self.show_deleted {
+            r = r.query(&[("showdeleted", "1")]);
+        }
 
-        let diff = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        let diff = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(diff)
     }
+
+    /// Looks up the metadata of the file with the given name within the given folder by listing the folder
+    async fn find_in_folder(
+        &self,
+        folder_id: u64,
+        name: &str,
+    ) -> Result<Metadata, Box<dyn std::error::Error + Send + Sync>> {
+        debug!(
+            "Requesting file metadata for file {} in folder {}",
+            name, folder_id
+        );
+        let listing = self
+            .client
+            .list_folder(folder_id)?
+            .showdeleted(self.show_deleted)
+            .get()
+            .await?;
+
+        let metadata = listing
+            .metadata
+            .map(|m| m.contents)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|m| !m.isfolder && m.name == name);
+
+        match metadata {
+            Some(m) => Ok(m),
+            None => Err(PCloudResult::FileNotFound)?,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -1314,12 +2207,26 @@ impl PCloudClient {
         &self,
         link: &pcloud_model::DownloadLink,
     ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        let refreshed;
+        let link = match &link.source {
+            Some(source) if link.is_expired() => {
+                debug!(
+                    "Download link for file {} expired, requesting a new one",
+                    source
+                );
+                refreshed = FileDownloadRequestBuilder::for_file(self, source.clone())?
+                    .get()
+                    .await?;
+                &refreshed
+            }
+            _ => link,
+        };
+
         if let Some(url) = link.into_url() {
             debug!("Downloading file link {}", url);
 
             // No authentication necessary!
-            // r = self.add_token(r);
-            let resp = self.client.get(url).send().await?;
+            let resp = self.decorate(self.client.get(url)).send().await?;
 
             Ok(resp)
         } else {
@@ -1327,6 +2234,158 @@ impl PCloudClient {
         }
     }
 
+    /// Streams the body of the given response into the writer and returns the number of bytes written
+    pub(crate) async fn write_response_to<W: AsyncWrite + Unpin>(
+        response: Response,
+        writer: &mut W,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        PCloudClient::write_response_inspecting(response, writer, |_| {}).await
+    }
+
+    /// Streams the body of the given response into the writer and passes each chunk to the inspector (e.g. to compute a checksum). Returns the number of bytes written
+    pub(crate) async fn write_response_inspecting<W: AsyncWrite + Unpin, F: FnMut(&[u8])>(
+        response: Response,
+        writer: &mut W,
+        mut inspect: F,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let mut response = response.error_for_status()?;
+        let expected = response.content_length();
+        let mut written: u64 = 0;
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    inspect(&chunk);
+                    writer.write_all(&chunk).await?;
+                    written += chunk.len() as u64;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Download aborted after {} bytes: {}", written, e);
+                    Err(PCloudResult::ConnectionBroken)?
+                }
+            }
+        }
+        writer.flush().await?;
+
+        if let Some(expected) = expected {
+            if written != expected {
+                warn!(
+                    "Download truncated: expected {} bytes, but got {}",
+                    expected, written
+                );
+                Err(PCloudResult::ConnectionBroken)?
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Downloads the given text file and lazily yields its lines (without line endings), so even huge files are never loaded into memory at once. Lines must be valid UTF-8.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub async fn read_lines<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<
+        impl Stream<Item = Result<String, Box<dyn std::error::Error + Send + Sync>>>,
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        let response = self.download_file(file_like).await?.error_for_status()?;
+
+        let lines = stream::try_unfold((Some(response), Vec::new()), PCloudClient::next_line);
+
+        Ok(lines)
+    }
+
+    /// Reads the next line from the buffer, fetching further chunks of the response when necessary. Lines are split on the raw bytes: since a newline byte never occurs within a multi-byte UTF-8 sequence, sequences split across chunks are decoded correctly.
+    async fn next_line(
+        (mut response, mut buffer): (Option<Response>, Vec<u8>),
+    ) -> Result<
+        Option<(String, (Option<Response>, Vec<u8>))>,
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        loop {
+            if let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                let mut line: Vec<u8> = buffer.drain(..=pos).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                let line = String::from_utf8(line)?;
+                return Ok(Some((line, (response, buffer))));
+            }
+
+            match response.as_mut() {
+                Some(r) => match r.chunk().await? {
+                    Some(chunk) => buffer.extend_from_slice(&chunk),
+                    None => response = None,
+                },
+                None if buffer.is_empty() => return Ok(None),
+                None => {
+                    // Last line without trailing newline
+                    let line = String::from_utf8(std::mem::take(&mut buffer))?;
+                    return Ok(Some((line, (response, buffer))));
+                }
+            }
+        }
+    }
+
+    /// Fetches the download link for the given revision of the file and streams its content into the writer. Returns the number of bytes written.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub async fn download_revision_to<T: FileDescriptor, W: AsyncWrite + Unpin>(
+        &self,
+        file_like: T,
+        revision_id: u64,
+        writer: &mut W,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let link = self
+            .get_download_link_for_file(file_like)?
+            .with_revision(revision_id)
+            .get()
+            .await?;
+        let response = self.download_link(&link).await?;
+        PCloudClient::write_response_to(response, writer).await
+    }
+
+    /// Downloads the latest revision of the file into the writer while computing its SHA-1 checksum, which is afterwards compared to the checksum reported by pCloud. Returns the number of bytes written. Fails with a ChecksumMismatchError if they differ, so silent truncation or corruption is detected. In this case the writer contains the corrupt data and the download can be retried. Fails with InternalError if pCloud reports no SHA-1 checksum to compare with.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    #[cfg(feature = "verify_checksum")]
+    pub async fn download_verified_to<T: FileDescriptor, W: AsyncWrite + Unpin>(
+        &self,
+        file_like: T,
+        writer: &mut W,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        use sha1::{Digest, Sha1};
+
+        // Make sure download and checksum refer to the same file
+        let (file_id, _) = self.get_file_id(file_like).await?;
+
+        let link = self.get_download_link_for_file(file_id)?.get().await?;
+        let response = self.download_link(&link).await?;
+
+        let mut hasher = Sha1::new();
+        let written =
+            PCloudClient::write_response_inspecting(response, writer, |chunk| hasher.update(chunk))
+                .await?;
+        let actual = format!("{:x}", hasher.finalize());
+
+        let checksums = self.checksum_file(file_id)?.get().await?;
+        let expected = match checksums.sha1 {
+            Some(sha1) => sha1.to_lowercase(),
+            None => {
+                warn!("pCloud reported no SHA-1 checksum for file {}", file_id);
+                Err(PCloudResult::InternalError)?
+            }
+        };
+
+        if expected != actual {
+            warn!(
+                "Checksum mismatch for file {}: expected {}, but got {}",
+                file_id, expected, actual
+            );
+            Err(pcloud_model::ChecksumMismatchError { expected, actual })?
+        }
+
+        Ok(written)
+    }
+
     /// Returns the file id (and the revision if given) of a PCloudFile. If the file_id is given, just return it. If a path is given, fetch the metadata with the file id.
     pub(crate) async fn get_file_id<T: FileDescriptor>(
         &self,
@@ -1337,7 +2396,14 @@ impl PCloudClient {
 
         if let Some(file_id) = file.file_id {
             Ok((file_id, rev))
+        } else if let Some(file_id) = file
+            .path
+            .as_ref()
+            .and_then(|path| self.cached_id(PathKind::File, path))
+        {
+            Ok((file_id, rev))
         } else {
+            let path = file.path.clone();
             let metadata = self.get_file_metadata(file).await?.metadata.unwrap();
 
             if metadata.isfolder {
@@ -1345,6 +2411,9 @@ impl PCloudClient {
             }
 
             if let Some(file_id) = metadata.fileid {
+                if let Some(path) = path {
+                    self.cache_id(PathKind::File, &path, file_id);
+                }
                 Ok((file_id, rev))
             } else {
                 Err(PCloudResult::NoFileIdOrPathProvided)?
@@ -1353,78 +2422,303 @@ impl PCloudClient {
     }
 
     /// Fetches the download link for the latest file revision and directly downloads the file.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub async fn download_file<'a, T: FileDescriptor>(
+    pub async fn download_file<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<Response, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
         let link = self.get_download_link_for_file(file_like)?.get().await?;
         self.download_link(&link).await
     }
 
-    /// Copies the given file to the given folder. Either set a target folder id and then the target with with_new_name or give a full new file path as target path
-    pub fn copy_file<'a, S: FileDescriptor, T: FolderDescriptor>(
+    /// Downloads the latest revision of the file and returns its content. Meant for small files (like configuration files), so files larger than 16 MiB are rejected with a FileTooLargeError. Use read_file_bytes_limited() to choose another limit or the streaming downloads for large files.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub async fn read_file_bytes<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.read_file_bytes_limited(file_like, DEFAULT_MAX_READ_SIZE)
+            .await
+    }
+
+    /// Downloads the latest revision of the file and returns its content. Fails with a FileTooLargeError if the file is larger than max_size bytes, before the content is buffered.  Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub async fn read_file_bytes_limited<T: FileDescriptor>(
+        &self,
+        file_like: T,
+        max_size: u64,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut response = self.download_file(file_like).await?.error_for_status()?;
+
+        if let Some(size) = response.content_length() {
+            if size > max_size {
+                Err(pcloud_model::FileTooLargeError { max_size })?
+            }
+        }
+
+        // The content length might be missing, so check while reading, too
+        let mut content = Vec::with_capacity(response.content_length().unwrap_or(0) as usize);
+        while let Some(chunk) = response.chunk().await? {
+            if (content.len() + chunk.len()) as u64 > max_size {
+                Err(pcloud_model::FileTooLargeError { max_size })?
+            }
+            content.extend_from_slice(&chunk);
+        }
+
+        Ok(content)
+    }
+
+    /// Copies the given file to the given folder. Either set a target folder id and then the target with with_new_name or give a full new file path as target path. A target path ending with `/` copies the file into this folder keeping its name, while any other path is taken as path of the new file.
+    pub fn copy_file<S: FileDescriptor, T: FolderDescriptor>(
         &self,
         file_like: S,
         target_folder_like: T,
-    ) -> Result<CopyFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CopyFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         CopyFileRequestBuilder::copy_file(self, file_like, target_folder_like)
     }
 
     /// Moves the given file to the given folder. Either set a target folder id and then the target with with_new_name or give a full new file path as target path
-    pub fn move_file<'a, S: FileDescriptor, T: FolderDescriptor>(
+    pub fn move_file<S: FileDescriptor, T: FolderDescriptor>(
         &self,
         file_like: S,
         target_folder_like: T,
-    ) -> Result<MoveFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<MoveFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         MoveFileRequestBuilder::move_file(self, file_like, target_folder_like)
     }
 
     /// Lists revisions for a given fileid / path
-    pub async fn list_file_revisions<'a, S: FileDescriptor>(
+    pub async fn list_file_revisions<S: FileDescriptor>(
         &self,
         file_like: S,
-    ) -> Result<RevisionList, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<RevisionList, Box<dyn std::error::Error + Send + Sync>> {
         ListRevisionsRequestBuilder::for_file(self, file_like)?
             .get()
             .await
     }
 
+    /// Reverts a file to the given revision, which becomes the current content of the file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub async fn revert_file<T: FileDescriptor>(
+        &self,
+        file_like: T,
+        revision_id: u64,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        RevertRevisionRequestBuilder::for_file(self, file_like, revision_id)?
+            .execute()
+            .await
+    }
+
+    /// Requests the metadata of a file with further options (like including deleted files). Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
+    pub fn stat_file<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<FileStatRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        FileStatRequestBuilder::for_file(self, file_like)
+    }
+
     /// Returns the metadata of a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub async fn get_file_metadata<'a, T: FileDescriptor>(
+    pub async fn get_file_metadata<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<FileOrFolderStat, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
         FileStatRequestBuilder::for_file(self, file_like)?
             .get()
             .await
     }
 
-    /// Requests deleting a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub async fn delete_file<'a, T: FileDescriptor>(
+    /// Checks if the given file exists. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata). Only errors other than a missing file (or a missing parent folder) are returned as error.
+    pub async fn file_exists<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        match self.stat_file(file_like)?.get().await {
+            Ok(_) => Ok(true),
+            Err(e) => match e.downcast_ref::<PCloudResult>() {
+                Some(PCloudResult::FileNotFound)
+                | Some(PCloudResult::ComponentOfTheParentDirectoryDoesNotExist)
+                | Some(PCloudResult::DirectoryDoesNotExist) => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Requests deleting a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata).
+    /// Depending on the account settings, the file is either moved to the trash (and can be restored from there) or purged immediately. Use delete_file_permanently() to make sure the file is gone for good.
+    pub async fn delete_file<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<FileOrFolderStat, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
         FileDeleteRequestBuilder::for_file(self, file_like)?
             .execute()
             .await
     }
 
+    /// Deletes a file like delete_file() and additionally reports whether it was moved to the trash or purged immediately (depending on the account settings), e.g. to decide if the trash has to be cleared, too. pCloud does not tell in its response, so the deleted file is looked up in the trash afterwards. In dry-run mode nothing is deleted and trashed is always false.
+    pub async fn delete_file_detailed<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<DeleteResult, Box<dyn std::error::Error + Send + Sync>> {
+        let deleted = self.delete_file(file_like).await?;
+
+        let trashed = match deleted.file_id() {
+            Some(file_id) if !self.is_dry_run() => {
+                match self.stat_file(file_id)?.show_deleted(true).get().await {
+                    Ok(stat) => stat.metadata.and_then(|m| m.isdeleted).unwrap_or(false),
+                    Err(e) => match e.downcast_ref::<PCloudResult>() {
+                        // File was purged immediately and is therefore not present in the trash
+                        Some(PCloudResult::FileNotFound) => false,
+                        _ => Err(e)?,
+                    },
+                }
+            }
+            _ => false,
+        };
+
+        Ok(DeleteResult {
+            metadata: deleted.metadata,
+            trashed,
+        })
+    }
+
+    /// Deletes a file and afterwards removes it from the trash, so it can not be restored anymore. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata).
+    /// Returns the metadata of the deleted file as returned by the delete operation.
+    pub async fn delete_file_permanently<T: FileDescriptor>(
+        &self,
+        file_like: T,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        if self.is_dry_run() {
+            // Neither resolve the file nor clear the trash, just log the delete request
+            return self.delete_file(file_like).await;
+        }
+
+        // The file id is required to identify the file in the trash, the path is not valid anymore after deletion
+        let (file_id, _) = self.get_file_id(file_like).await?;
+
+        let deleted = self.delete_file(file_id).await?;
+
+        let mut r = self.client.get(format!("{}/trash_clear", self.api_host));
+        debug!("Clearing file {} from trash", file_id);
+        r = r.query(&[("fileid", file_id)]);
+        r = self.add_token(r);
+
+        let result = r
+            .send()
+            .await?
+            .json::<TrashClearResponse>()
+            .await?
+            .assert_ok();
+
+        match result {
+            Ok(_) => Ok(deleted),
+            // File was purged immediately and is therefore not present in the trash
+            Err(PCloudResult::FileNotFound) => Ok(deleted),
+            Err(e) => Err(e)?,
+        }
+    }
+
+    /// Sets the modification time (and optionally the creation time) of a file without uploading its content again. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata). The creation time can only be set together with the modification time.
+    /// pCloud has no dedicated endpoint for this and does not copy a file onto itself, so the file is copied to a temporary name with the new times and then copied back over the original file (which keeps its id and gets a new revision). The temporary copy is deleted permanently afterwards. Returns the updated metadata of the file.
+    pub async fn set_file_times<T: FileDescriptor, Tz>(
+        &self,
+        file_like: T,
+        mtime: &DateTime<Tz>,
+        ctime: Option<&DateTime<Tz>>,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>>
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        let file = self
+            .get_file_metadata(file_like)
+            .await?
+            .metadata
+            .ok_or(PCloudResult::FileNotFound)?;
+        if file.isfolder {
+            Err(PCloudResult::FileNotFound)?
+        }
+        let file_id = file.fileid.ok_or(PCloudResult::FileNotFound)?;
+        let folder_id = file
+            .parentfolderid
+            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+
+        if self.is_dry_run() {
+            // Only log the first copy, the following requests depend on its result
+            let mut copy = self.copy_file(file_id, folder_id)?.mtime(mtime);
+            if let Some(ctime) = ctime {
+                copy = copy.ctime(ctime);
+            }
+            return copy.execute().await;
+        }
+
+        let temp_name = format!(".{}.{}", file.name, Uuid::new_v4());
+        let mut copy = self
+            .copy_file(file_id, folder_id)?
+            .with_new_name(&temp_name)
+            .mtime(mtime);
+        if let Some(ctime) = ctime {
+            copy = copy.ctime(ctime);
+        }
+        let temp_id = copy
+            .execute()
+            .await?
+            .file_id()
+            .ok_or(PCloudResult::InternalError)?;
+        debug!("Copied file {} to {} to set its times", file_id, temp_name);
+
+        let mut copy = self
+            .copy_file(temp_id, folder_id)?
+            .with_new_name(&file.name)
+            .overwrite(true)
+            .mtime(mtime);
+        if let Some(ctime) = ctime {
+            copy = copy.ctime(ctime);
+        }
+        let copied = copy.execute().await;
+
+        // Never leave the temporary copy behind, even if copying it back failed
+        let deleted = self.delete_file_permanently(temp_id).await;
+        let copied = copied?;
+        deleted?;
+
+        Ok(copied)
+    }
+
     /// Requests the checksums of a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub fn checksum_file<'a, T: FileDescriptor>(
+    pub fn checksum_file<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<ChecksumFileRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<ChecksumFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         ChecksumFileRequestBuilder::for_file(self, file_like)
     }
 
     /// Returns the public link for a pCloud file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub fn get_public_link_for_file<'a, T: FileDescriptor>(
+    pub fn get_public_link_for_file<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<PublicFileLinkRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<PublicFileLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         PublicFileLinkRequestBuilder::for_file(&self, file_like)
     }
 
+    /// Lists all public links of the user including their access statistics
+    pub async fn list_public_links(
+        &self,
+    ) -> Result<pcloud_model::PublicLinkList, Box<dyn std::error::Error + Send + Sync>> {
+        let r = self.client.get(format!("{}/listpublinks", self.api_host));
+
+        debug!("Requesting public links");
+        let result = self.execute_json::<pcloud_model::PublicLinkList>(r).await?;
+        Ok(result)
+    }
+
+    /// Returns the total number of downloads and the total traffic of the public link with the given id (as part of its PublicLinkInfo). pCloud offers no history of the accesses over time, sample the totals periodically to track them. Fails with FileNotFound, if there is no such link.
+    pub async fn public_link_totals(
+        &self,
+        link_id: u64,
+    ) -> Result<pcloud_model::PublicLinkInfo, Box<dyn std::error::Error + Send + Sync>> {
+        let links = self.list_public_links().await?;
+
+        match links.publinks.into_iter().find(|l| l.linkid == link_id) {
+            Some(link) => Ok(link),
+            None => Err(PCloudResult::FileNotFound)?,
+        }
+    }
+
     /// Returns the public download link for a public file link
     pub async fn get_public_download_link_for_file(
         &self,
@@ -1435,24 +2729,140 @@ impl PCloudClient {
             .await
     }
 
+    /// Shows the metadata of a public link identified by its code (or short code). For public folder links the metadata contains the shared folder tree. Works with an anonymous client, too.
+    pub fn show_public_link(&self, code: &str) -> ShowPublicLinkRequestBuilder {
+        ShowPublicLinkRequestBuilder::for_public_link(self, code)
+    }
+
+    /// Resolves a short code (or a full short link like `https://pc.cd/<shortcode>`) of a public link into the metadata of the linked file or folder. Works with an anonymous client, too.
+    pub async fn resolve_short_link(
+        &self,
+        shortcode: &str,
+    ) -> Result<FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let code = shortcode
+            .trim()
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+
+        if code.is_empty() {
+            Err(PCloudResult::InvalidFileOrFolderName)?
+        }
+
+        ShowPublicLinkRequestBuilder::for_public_link(self, code)
+            .get()
+            .await
+    }
+
+    /// Returns the download link for a public file link identified by its code (or short code). Works with an anonymous client, too.
+    pub async fn download_public_file(
+        &self,
+        code: &str,
+    ) -> Result<pcloud_model::DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
+        PublicFileDownloadRequestBuilder::for_public_file(self, code)
+            .get()
+            .await
+    }
+
+    /// Copies the file of a public link (identified by its code or short code) into the given folder of the user. The copy is done server-side. For public folder links the file id has to be set. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata) as target.
+    pub fn save_public_file_to<T: FolderDescriptor>(
+        &self,
+        code: &str,
+        target_folder_like: T,
+    ) -> Result<SavePublicFileRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        SavePublicFileRequestBuilder::save_to(self, code, target_folder_like)
+    }
+
+    /// Requests the thumbnail of a public file link (or of the file with the given id within a public folder link) identified by its code (or short code). Size is given as WIDTHxHEIGHT (like 256x256). Works with an anonymous client, too.
+    pub fn public_thumbnail(
+        &self,
+        code: &str,
+        file_id: Option<u64>,
+        size: &str,
+    ) -> PublicThumbnailRequestBuilder {
+        PublicThumbnailRequestBuilder::for_public_file(self, code, file_id, size)
+    }
+
+    /// Returns the download link for a file within a public folder link identified by its code (or short code). Works with an anonymous client, too.
+    pub async fn download_public_file_in_folder(
+        &self,
+        code: &str,
+        file_id: u64,
+    ) -> Result<pcloud_model::DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
+        PublicFileDownloadRequestBuilder::for_file_in_public_folder(self, code, file_id)
+            .get()
+            .await
+    }
+
+    /// Returns the progress of a running upload started with the given progress hash (see UploadRequestBuilder::with_progress_hash())
+    pub async fn upload_progress(
+        &self,
+        progress_hash: &str,
+    ) -> Result<UploadProgressResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self.client.get(format!("{}/uploadprogress", self.api_host));
+
+        r = r.query(&[("progresshash", progress_hash)]);
+
+        let result = self.execute_json::<UploadProgressResponse>(r).await?;
+        Ok(result)
+    }
+
+    /// Returns the download links for multiple files. pCloud has no batch endpoint, so the links are requested concurrently (at most 8 at once). The results are returned in the order of the given files, a failure for one file does not affect the others. Accepts either file ids (u64), file paths (String) or any other pCloud objects describing a file (like Metadata)
+    pub async fn get_download_links<T: FileDescriptor, I: IntoIterator<Item = T>>(
+        &self,
+        files: I,
+    ) -> Vec<Result<pcloud_model::DownloadLink, Box<dyn std::error::Error + Send + Sync>>> {
+        stream::iter(files.into_iter().map(|file_like| async move {
+            self.get_download_link_for_file(file_like)?.get().await
+        }))
+        .buffered(DOWNLOAD_LINK_CONCURRENCY)
+        .collect()
+        .await
+    }
+
     /// Returns the download link for a file. Accepts either a file id (u64), a file path (String) or any other pCloud object describing a file (like Metadata)
-    pub fn get_download_link_for_file<'a, T: FileDescriptor>(
+    pub fn get_download_link_for_file<T: FileDescriptor>(
         &self,
         file_like: T,
-    ) -> Result<FileDownloadRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<FileDownloadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         FileDownloadRequestBuilder::for_file(self, file_like)
     }
 
     /// Uploads files into a folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
-    pub fn upload_file_into_folder<'a, T: FolderDescriptor>(
+    pub fn upload_file_into_folder<T: FolderDescriptor>(
         &self,
         folder_like: T,
-    ) -> Result<UploadRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<UploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         UploadRequestBuilder::into_folder(self, folder_like)
     }
 
+    /// Uploads a single file to the given target. The target must either be described by its folder id and name (see PCloudFile::in_folder()) or by its full path.
+    pub fn upload_file_as<T: Into<Body>>(
+        &self,
+        file: PCloudFile,
+        body: T,
+    ) -> Result<UploadRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        if let (Some(folder_id), Some(name)) = (file.folder_id, &file.name) {
+            Ok(UploadRequestBuilder::into_folder(self, folder_id)?.with_file(name, body))
+        } else if let Some((parent, name)) = file.path.as_ref().and_then(|p| p.rsplit_once('/')) {
+            if name.is_empty() {
+                Err(PCloudResult::InvalidFileOrFolderName)?
+            }
+            let parent = if parent.is_empty() { "/" } else { parent };
+            Ok(UploadRequestBuilder::into_folder(self, parent)?.with_file(name, body))
+        } else {
+            Err(PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
     /// Creates a Tree required for some requests (like building a zip file)
     pub fn create_tree(&self) -> Tree {
         Tree::create(self)
     }
+
+    /// Creates a Tree containing all entries of the given folder listing (see Tree::add_listing()), e.g. to zip everything just listed
+    pub fn create_tree_from_listing(&self, listing: &FileOrFolderStat) -> Tree {
+        Tree::create(self).add_listing(listing)
+    }
 }
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use log::debug;
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
+};
+
+use crate::{
+    folder_ops::{FolderDescriptor, PCloudFolder},
+    pcloud_client::PCloudClient,
+    pcloud_model::{self, AlreadyExistsError, Metadata, PCloudResult},
+};
+
+/// Default number of files copied concurrently
+const DEFAULT_COPY_CONCURRENCY: usize = 4;
+
+/// Overall progress of a folder merge
+#[derive(Debug, Clone)]
+pub struct MergeProgress {
+    /// Number of files copied so far
+    pub files_done: u64,
+    /// Total number of files to copy (without the skipped ones)
+    pub files_total: u64,
+    /// Number of bytes copied so far (only completed files are counted)
+    pub bytes_done: u64,
+    /// Total number of bytes to copy
+    pub bytes_total: u64,
+    /// Path (relative to the source folder) of the file completed last
+    pub current: String,
+}
+
+/// Result of a folder merge
+#[derive(Debug)]
+pub struct MergedFolder {
+    /// Id of the target folder
+    pub folder_id: u64,
+    /// Number of folders created below the target folder (existing folders are not counted)
+    pub folders: u64,
+    /// Metadata of all copied files (in the target folder)
+    pub copied: Vec<Metadata>,
+    /// Metadata of all source files skipped, because they already existed in the target folder
+    pub skipped: Vec<Metadata>,
+}
+
+/// Source file to copy
+struct PendingCopy {
+    /// Id of the source file
+    file_id: u64,
+    /// Path relative to the source folder (only for progress reporting)
+    relative: String,
+    /// Size of the file in bytes
+    size: u64,
+    /// Id of the target folder to copy the file into
+    folder_id: u64,
+}
+
+pub struct CopyFolderMergeRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    /// Folder whose contents are copied
+    source: PCloudFolder,
+    /// Existing folder to merge the contents into
+    target: PCloudFolder,
+    /// If set, files already existing in the target are overwritten
+    overwrite: bool,
+    /// If set, files already existing in the target are skipped
+    skip_existing: bool,
+    /// Number of files copied concurrently
+    concurrency: usize,
+}
+
+#[allow(dead_code)]
+impl CopyFolderMergeRequestBuilder {
+    pub(crate) fn merge<S: FolderDescriptor, T: FolderDescriptor>(
+        client: &PCloudClient,
+        folder_like: S,
+        target_folder_like: T,
+    ) -> Result<CopyFolderMergeRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let source = folder_like.to_folder()?;
+        let target = target_folder_like.to_folder()?;
+
+        if (!source.is_empty() || source.is_child()) && (!target.is_empty() || target.is_child()) {
+            Ok(CopyFolderMergeRequestBuilder {
+                client: client.clone(),
+                source,
+                target,
+                overwrite: true,
+                skip_existing: false,
+                concurrency: DEFAULT_COPY_CONCURRENCY,
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// If set (default true), files already existing in the target are overwritten. If neither overwrite nor skip_existing is set, the merge fails with an AlreadyExistsError before anything is copied.
+    pub fn overwrite(mut self, value: bool) -> CopyFolderMergeRequestBuilder {
+        self.overwrite = value;
+        self
+    }
+
+    /// If set, files already existing in the target are skipped (takes precedence over overwrite)
+    pub fn skip_existing(mut self, value: bool) -> CopyFolderMergeRequestBuilder {
+        self.skip_existing = value;
+        self
+    }
+
+    /// Number of files copied concurrently (defaults to 4)
+    pub fn concurrency(mut self, value: usize) -> CopyFolderMergeRequestBuilder {
+        self.concurrency = value.max(1);
+        self
+    }
+
+    /// Merges the contents of the source folder into the target folder
+    pub async fn execute(self) -> Result<MergedFolder, Box<dyn std::error::Error + Send + Sync>> {
+        self.run(None).await
+    }
+
+    /// Starts merging in the background and notifies the user of the progress after each copied file. If the receiver does not keep up, intermediate updates are skipped. The handle returns the result once the merge is finished.
+    pub fn execute_with_progress(
+        self,
+    ) -> (
+        JoinHandle<Result<MergedFolder, Box<dyn std::error::Error + Send + Sync>>>,
+        Receiver<MergeProgress>,
+    ) {
+        let (tx, rx) = mpsc::channel::<MergeProgress>(32);
+
+        let handle = tokio::spawn(self.run(Some(tx)));
+
+        (handle, rx)
+    }
+
+    /// Lists both trees, creates the missing folders and copies the files
+    async fn run(
+        self,
+        progress: Option<Sender<MergeProgress>>,
+    ) -> Result<MergedFolder, Box<dyn std::error::Error + Send + Sync>> {
+        let client = self.client;
+
+        // Child descriptors (parent id and name) can not be listed directly
+        let source_id = client.get_folder_id(self.source).await?;
+        let target_id = client.get_folder_id(self.target).await?;
+
+        let source = client
+            .list_folder(source_id)?
+            .recursive(true)
+            .get()
+            .await?
+            .metadata
+            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+        let target = client
+            .list_folder(target_id)?
+            .recursive(true)
+            .get()
+            .await?
+            .metadata
+            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+        let root_id = target.folderid.ok_or(PCloudResult::DirectoryDoesNotExist)?;
+
+        // Both trees are walked together, missing folders are created top-down. All conflicts are detected before the first file is copied (missing folders might be created already).
+        let mut files: Vec<PendingCopy> = Vec::new();
+        let mut skipped: Vec<Metadata> = Vec::new();
+        let mut folders: u64 = 0;
+        let mut pending: Vec<(Metadata, Option<Metadata>, u64, String)> =
+            vec![(source, Some(target), root_id, String::new())];
+
+        while let Some((source_folder, target_folder, folder_id, path)) = pending.pop() {
+            let mut existing: HashMap<String, Metadata> = target_folder
+                .map(|f| {
+                    f.contents
+                        .into_iter()
+                        .map(|m| (m.name.clone(), m))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            for entry in source_folder.contents {
+                let relative = format!("{}/{}", path, entry.name);
+
+                let existing_entry = existing.remove(&entry.name);
+
+                if entry.isfolder {
+                    match existing_entry {
+                        Some(folder) if folder.isfolder => {
+                            let sub_folder_id =
+                                folder.folderid.ok_or(PCloudResult::DirectoryDoesNotExist)?;
+                            pending.push((entry, Some(folder), sub_folder_id, relative));
+                        }
+                        Some(file) => Err(AlreadyExistsError { metadata: file })?,
+                        None => {
+                            let created = client
+                                .create_folder(folder_id, &entry.name)?
+                                .if_not_exists(true)
+                                .execute()
+                                .await?;
+                            let sub_folder_id = created
+                                .metadata
+                                .and_then(|m| m.folderid)
+                                .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+                            debug!("Created folder {} for {}", sub_folder_id, relative);
+
+                            folders += 1;
+                            pending.push((entry, None, sub_folder_id, relative));
+                        }
+                    }
+                } else {
+                    match existing_entry {
+                        Some(folder) if folder.isfolder => {
+                            Err(AlreadyExistsError { metadata: folder })?
+                        }
+                        Some(_) if self.skip_existing => {
+                            debug!("Skipping {}, it already exists", relative);
+                            skipped.push(entry);
+                        }
+                        Some(file) if !self.overwrite => {
+                            Err(AlreadyExistsError { metadata: file })?
+                        }
+                        _ => files.push(PendingCopy {
+                            file_id: entry.fileid.ok_or(PCloudResult::FileNotFound)?,
+                            relative,
+                            size: entry.size.unwrap_or(0),
+                            folder_id,
+                        }),
+                    }
+                }
+            }
+        }
+
+        let files_total = files.len() as u64;
+        let bytes_total: u64 = files.iter().map(|f| f.size).sum();
+        let mut files_done: u64 = 0;
+        let mut bytes_done: u64 = 0;
+
+        let copied: Vec<Metadata> = stream::iter(files.into_iter().map(|file| {
+            let client = &client;
+            async move {
+                let copied = client
+                    .copy_file(file.file_id, file.folder_id)?
+                    .overwrite(true)
+                    .execute()
+                    .await?;
+                let metadata = copied.metadata.ok_or(PCloudResult::InternalError)?;
+                Ok::<(PendingCopy, Metadata), Box<dyn std::error::Error + Send + Sync>>((
+                    file, metadata,
+                ))
+            }
+        }))
+        .buffer_unordered(self.concurrency)
+        .map_ok(|(file, metadata)| {
+            files_done += 1;
+            bytes_done += file.size;
+            debug!("Copied {} ({}/{})", file.relative, files_done, files_total);
+
+            if let Some(tx) = &progress {
+                // Never block the copy, the next update contains the overall progress anyway
+                let _ = tx.try_send(MergeProgress {
+                    files_done,
+                    files_total,
+                    bytes_done,
+                    bytes_total,
+                    current: file.relative,
+                });
+            }
+            metadata
+        })
+        .try_collect()
+        .await?;
+
+        Ok(MergedFolder {
+            folder_id: root_id,
+            folders,
+            copied,
+            skipped,
+        })
+    }
+}
+
+impl PCloudClient {
+    /// Merges the contents of a folder into an existing folder, unlike copy_folder() which copies the whole tree with a single request. Sub folders are merged recursively (existing folders are reused), the files are copied one by one (concurrently) and files already existing in the target are overwritten or skipped. Accepts either folder ids (u64), folder paths (String) or any other pCloud objects describing a folder (like Metadata).
+    pub fn copy_folder_merge<S: FolderDescriptor, T: FolderDescriptor>(
+        &self,
+        folder_like: S,
+        target_folder_like: T,
+    ) -> Result<CopyFolderMergeRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        CopyFolderMergeRequestBuilder::merge(self, folder_like, target_folder_like)
+    }
+}
@@ -1,10 +1,17 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
 
 use crate::{
+    path_cache::PathKind,
     pcloud_client::PCloudClient,
-    pcloud_model::{self, FileOrFolderStat, Metadata, PCloudResult, WithPCloudResult},
+    pcloud_model::{
+        self, FileCategory, FileOrFolderStat, FolderNode, FolderSize, Metadata, PCloudResult,
+        WithPCloudResult,
+    },
 };
 use log::debug;
+use reqwest::RequestBuilder;
+use serde::{de::IgnoredAny, Deserialize};
+use uuid::Uuid;
 
 /// Generic description of a pCloud folder. Either by its file id (preferred) or by its path
 pub trait FolderDescriptor {
@@ -66,12 +73,51 @@ pub struct PCloudFolder {
     pub folder_id: Option<u64>,
     /// Path of the target folder
     pub path: Option<String>,
+    /// ID of the parent folder (together with name)
+    pub parent_folder_id: Option<u64>,
+    /// Name of the folder within the parent folder (together with parent_folder_id)
+    pub name: Option<String>,
 }
 
 impl PCloudFolder {
+    /// Describes the folder (or target) with the given name within the folder with the given id. Such a descriptor can be used as target of copy or move operations (sent as tofolderid and toname) and is resolved by listing the parent folder, where a folder id is required.
+    pub fn child(parent_folder_id: u64, name: &str) -> PCloudFolder {
+        PCloudFolder {
+            folder_id: None,
+            path: None,
+            parent_folder_id: Some(parent_folder_id),
+            name: Some(name.to_string()),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.folder_id.is_none() && self.path.is_none()
     }
+
+    /// Returns the id of the parent folder, if it is known from the descriptor (folders described by parent folder id and name). The root folder has no parent.
+    pub fn parent_id(&self) -> Option<u64> {
+        if self.folder_id == Some(0) || self.path.as_deref() == Some("/") {
+            None
+        } else {
+            self.parent_folder_id
+        }
+    }
+
+    /// Is the folder described by its parent folder id and its name?
+    pub fn is_child(&self) -> bool {
+        self.parent_folder_id.is_some() && self.name.is_some()
+    }
+
+    /// Returns the normalized path of a file or folder with the given name within this folder. Only possible if the path of this folder is known (or it is the root folder).
+    pub fn child_path(&self, name: &str) -> Result<String, PCloudResult> {
+        if let Some(path) = &self.path {
+            pcloud_model::join_path(path, name)
+        } else if self.folder_id == Some(0) {
+            pcloud_model::join_path("/", name)
+        } else {
+            Err(PCloudResult::InvalidPath)
+        }
+    }
 }
 
 impl Display for PCloudFolder {
@@ -80,6 +126,8 @@ impl Display for PCloudFolder {
             write!(f, "{}", folder_id)
         } else if let Some(path) = &self.path {
             write!(f, "{}", path)
+        } else if let (Some(parent_folder_id), Some(name)) = (self.parent_folder_id, &self.name) {
+            write!(f, "{}/{}", parent_folder_id, name)
         } else {
             write!(f, "[Empty pCloud folder descriptor!]")
         }
@@ -96,12 +144,16 @@ impl TryFrom<&str> for PCloudFolder {
             Ok(PCloudFolder {
                 folder_id: Some(0),
                 path: None,
+                parent_folder_id: None,
+                name: None,
             })
         } else if value.starts_with("/") {
             // File paths must always be absolute paths
             Ok(PCloudFolder {
                 folder_id: None,
                 path: Some(value.to_string()),
+                parent_folder_id: None,
+                name: None,
             })
         } else {
             Err(PCloudResult::InvalidPath)?
@@ -119,12 +171,16 @@ impl TryFrom<String> for PCloudFolder {
             Ok(PCloudFolder {
                 folder_id: Some(0),
                 path: None,
+                parent_folder_id: None,
+                name: None,
             })
         } else if value.starts_with("/") {
             // File paths must always be absolute paths
             Ok(PCloudFolder {
                 folder_id: None,
                 path: Some(value),
+                parent_folder_id: None,
+                name: None,
             })
         } else {
             Err(PCloudResult::InvalidPath)?
@@ -138,6 +194,8 @@ impl From<u64> for PCloudFolder {
         PCloudFolder {
             folder_id: Some(value),
             path: None,
+            parent_folder_id: None,
+            name: None,
         }
     }
 }
@@ -148,6 +206,8 @@ impl From<&u64> for PCloudFolder {
         PCloudFolder {
             folder_id: Some(value.clone()),
             path: None,
+            parent_folder_id: None,
+            name: None,
         }
     }
 }
@@ -163,6 +223,8 @@ impl TryFrom<&Metadata> for PCloudFolder {
             Ok(PCloudFolder {
                 folder_id: value.folderid,
                 path: None,
+                parent_folder_id: None,
+                name: None,
             })
         }
     }
@@ -193,10 +255,10 @@ pub struct DeleteFolderRequestBuilder {
 
 #[allow(dead_code)]
 impl DeleteFolderRequestBuilder {
-    pub(crate) fn for_folder<'a, T: FolderDescriptor>(
+    pub(crate) fn for_folder<T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like: T,
-    ) -> Result<DeleteFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<DeleteFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = folder_like.to_folder()?;
 
         if !f.is_empty() {
@@ -210,6 +272,26 @@ impl DeleteFolderRequestBuilder {
         }
     }
 
+    /// Computes what delete_recursive() would delete, without deleting anything: the number of files and folders (including the folder itself) are reported in the shape of the actual delete result, so a user interface can ask for confirmation first. Walks the whole folder tree (see PCloudClient::folder_size()).
+    pub async fn preview(
+        &self,
+    ) -> Result<pcloud_model::FolderRecursivlyDeleted, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let folder = PCloudFolder {
+            folder_id: self.folder_id,
+            path: self.path.clone(),
+            parent_folder_id: None,
+            name: None,
+        };
+        let size = self.client.folder_size(folder, false).await?;
+
+        Ok(pcloud_model::FolderRecursivlyDeleted {
+            result: PCloudResult::Ok,
+            deletedfiles: Some(size.files),
+            deletedfolders: Some(size.folders + 1),
+        })
+    }
+
     /// Deletes the folder and all its content recursively
     pub async fn delete_recursive(
         self,
@@ -219,7 +301,7 @@ impl DeleteFolderRequestBuilder {
 
         let mut r = self.client.client.get(url);
 
-        if let Some(p) = self.path {
+        if let Some(p) = &self.path {
             debug!("Deleting folder {} recursively", p);
             r = r.query(&[("path", p)]);
         }
@@ -229,14 +311,18 @@ impl DeleteFolderRequestBuilder {
             r = r.query(&[("folderid", id)]);
         }
 
-        r = self.client.add_token(r);
+        if self.client.is_dry_run() {
+            self.client.log_dry_run(r);
+            return Ok(pcloud_model::FolderRecursivlyDeleted::dry_run());
+        }
+
+        self.client
+            .invalidate_cached_folder(self.folder_id, self.path.as_deref());
 
-        let stat = r
-            .send()
-            .await?
-            .json::<pcloud_model::FolderRecursivlyDeleted>()
-            .await?
-            .assert_ok()?;
+        let stat = self
+            .client
+            .execute_json::<pcloud_model::FolderRecursivlyDeleted>(r)
+            .await?;
         Ok(stat)
     }
 
@@ -248,7 +334,7 @@ impl DeleteFolderRequestBuilder {
 
         let mut r = self.client.client.get(url);
 
-        if let Some(p) = self.path {
+        if let Some(p) = &self.path {
             debug!("Deleting folder {} if empty", p);
             r = r.query(&[("path", p)]);
         }
@@ -258,14 +344,18 @@ impl DeleteFolderRequestBuilder {
             r = r.query(&[("folderid", id)]);
         }
 
-        r = self.client.add_token(r);
+        if self.client.is_dry_run() {
+            self.client.log_dry_run(r);
+            return Ok(pcloud_model::FileOrFolderStat::dry_run());
+        }
 
-        let stat = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        self.client
+            .invalidate_cached_folder(self.folder_id, self.path.as_deref());
+
+        let stat = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(stat)
     }
 }
@@ -281,15 +371,17 @@ pub struct CreateFolderRequestBuilder {
     name: String,
     /// Creates a folder if the folder doesn't exist or returns the existing folder's metadata.
     if_not_exists: bool,
+    /// Creates missing parent folders of the given path first
+    mkdirs: bool,
 }
 
 #[allow(dead_code)]
 impl CreateFolderRequestBuilder {
-    pub(crate) fn for_folder<'a, T: FolderDescriptor>(
+    pub(crate) fn for_folder<T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like_parent: T,
         name: &str,
-    ) -> Result<CreateFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CreateFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = folder_like_parent.to_folder()?;
 
         if !f.is_empty() {
@@ -299,6 +391,7 @@ impl CreateFolderRequestBuilder {
                 client: client.clone(),
                 name: name.to_string(),
                 if_not_exists: true,
+                mkdirs: false,
             })
         } else {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
@@ -311,10 +404,23 @@ impl CreateFolderRequestBuilder {
         self
     }
 
+    /// If true, all missing parent folders of the given parent path are created first (like `mkdir -p`). Has no effect if the parent folder is given by id. Defaults to false.
+    pub fn mkdirs(mut self, value: bool) -> CreateFolderRequestBuilder {
+        self.mkdirs = value;
+        self
+    }
+
     /// Creates the folder
     pub async fn execute(
-        self,
+        mut self,
     ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        if self.mkdirs && self.folder_id.is_none() {
+            if let Some(p) = self.path.take() {
+                let (parent_folder_id, _) = self.client.create_folders_recursive(&p).await?;
+                self.folder_id = Some(parent_folder_id);
+            }
+        }
+
         let url = if self.if_not_exists {
             format!("{}/createfolderifnotexists", self.client.api_host)
         } else {
@@ -335,14 +441,10 @@ impl CreateFolderRequestBuilder {
 
         r = r.query(&[("name", self.name)]);
 
-        r = self.client.add_token(r);
-
-        let stat = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        let stat = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(stat)
     }
 }
@@ -371,11 +473,11 @@ pub struct CopyFolderRequestBuilder {
 #[allow(dead_code)]
 impl CopyFolderRequestBuilder {
     /// Copies a folder identified by folderid or path to either topath or tofolderid.
-    pub(crate) fn copy_folder<'a, S: FolderDescriptor, T: FolderDescriptor>(
+    pub(crate) fn copy_folder<S: FolderDescriptor, T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like: S,
         target_folder_like: T,
-    ) -> Result<CopyFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CopyFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let source: PCloudFolder = folder_like.to_folder()?;
         let target: PCloudFolder = target_folder_like.to_folder()?;
 
@@ -414,6 +516,28 @@ impl CopyFolderRequestBuilder {
         self
     }
 
+    /// Adds the query parameters for the given flags (see overwrite(), skip_existing() and copy_content_only()) to the copyfolder request
+    pub fn add_flags(
+        mut r: RequestBuilder,
+        overwrite: bool,
+        skip_existing: bool,
+        copy_content_only: bool,
+    ) -> RequestBuilder {
+        if !overwrite {
+            r = r.query(&[("noover", "1")]);
+        }
+
+        if skip_existing {
+            r = r.query(&[("skipexisting", "1")]);
+        }
+
+        if copy_content_only {
+            r = r.query(&[("copycontentonly", "1")]);
+        }
+
+        r
+    }
+
     /// Execute the copy operation
     pub async fn execute(
         self,
@@ -443,26 +567,17 @@ impl CopyFolderRequestBuilder {
             r = r.query(&[("toname", v)]);
         }
 
-        if !self.overwrite {
-            r = r.query(&[("noover", "1")]);
-        }
-
-        if !self.skip_existing {
-            r = r.query(&[("skipexisting", "1")]);
-        }
+        r = CopyFolderRequestBuilder::add_flags(
+            r,
+            self.overwrite,
+            self.skip_existing,
+            self.copy_content_only,
+        );
 
-        if !self.copy_content_only {
-            r = r.query(&[("copycontentonly", "1")]);
-        }
-
-        r = self.client.add_token(r);
-
-        let result = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        let result = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(result)
     }
 }
@@ -480,27 +595,41 @@ pub struct MoveFolderRequestBuilder {
     to_folder_id: Option<u64>,
     /// New file name
     to_name: Option<String>,
+    /// What to do if the destination already contains a folder with the same name
+    on_conflict: MoveConflict,
+}
+
+/// Strategy if the destination of a folder move already contains a folder with the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveConflict {
+    /// Let pCloud fail the move (default)
+    Fail,
+    /// Merge the contents of the source folder into the existing folder. Files with the same name are replaced, sub folders with the same name are merged recursively. Finally the merged source folders are deleted bottom-up, which fails if any of them is not empty (e.g. because something was added meanwhile).
+    Merge,
+    /// Move the source folder next to the existing folder (using a temporary name), then delete the existing folder (recursively!) and finally rename the source folder. The existing folder is kept if the source folder cannot be moved.
+    Replace,
 }
 
 #[allow(dead_code)]
 impl MoveFolderRequestBuilder {
     /// Renames (and/or moves) a folder identified by folderid or path to either topath (if topath is a existing folder to place source folder without new name for the folder it MUST end with slash - /newpath/) or tofolderid/toname (one or both can be provided).
-    pub(crate) fn move_folder<'a, S: FolderDescriptor, T: FolderDescriptor>(
+    pub(crate) fn move_folder<S: FolderDescriptor, T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like: S,
         target_folder_like: T,
-    ) -> Result<MoveFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<MoveFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let source: PCloudFolder = folder_like.to_folder()?;
         let target: PCloudFolder = target_folder_like.to_folder()?;
 
-        if !source.is_empty() && !target.is_empty() {
+        if !source.is_empty() && (!target.is_empty() || target.is_child()) {
             Ok(MoveFolderRequestBuilder {
                 from_path: source.path,
                 from_folder_id: source.folder_id,
                 to_path: target.path,
-                to_folder_id: target.folder_id,
+                to_folder_id: target.folder_id.or(target.parent_folder_id),
                 client: client.clone(),
-                to_name: None,
+                to_name: target.name,
+                on_conflict: MoveConflict::Fail,
             })
         } else {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
@@ -513,16 +642,192 @@ impl MoveFolderRequestBuilder {
         self
     }
 
+    /// What to do if the destination already contains a folder with the same name (defaults to MoveConflict::Fail). Merge and Replace are implemented client-side with several requests, so they are not atomic.
+    pub fn on_conflict(mut self, value: MoveConflict) -> MoveFolderRequestBuilder {
+        self.on_conflict = value;
+        self
+    }
+
     // Execute the move operation
     pub async fn execute(
         self,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        match self.on_conflict {
+            MoveConflict::Fail => self.rename_folder().await,
+            MoveConflict::Merge | MoveConflict::Replace => self.move_with_conflict_handling().await,
+        }
+    }
+
+    /// Determines the id of the destination parent folder and the name of the folder after the move
+    async fn resolve_destination(
+        &self,
+        source_name: &str,
+    ) -> Result<(u64, String), Box<dyn std::error::Error + Send + Sync>> {
+        let name = self.to_name.clone().unwrap_or(source_name.to_string());
+
+        if let Some(to_folder_id) = self.to_folder_id {
+            Ok((to_folder_id, name))
+        } else if let Some(to_path) = &self.to_path {
+            if to_path.ends_with('/') {
+                // Existing folder to place the source folder in
+                let parent = match to_path.trim_end_matches('/') {
+                    "" => "/",
+                    p => p,
+                };
+                Ok((self.client.get_folder_id(parent).await?, name))
+            } else {
+                // Full path of the folder after the move
+                let (parent, name) = to_path.rsplit_once('/').ok_or(PCloudResult::InvalidPath)?;
+                let parent = if parent.is_empty() { "/" } else { parent };
+                Ok((self.client.get_folder_id(parent).await?, name.to_string()))
+            }
+        } else {
+            Err(PCloudResult::NoFileIdOrPathProvided)?
+        }
+    }
+
+    /// Returns the id of the folder with the given name within the given parent folder, if present
+    async fn find_sub_folder(
+        client: &PCloudClient,
+        parent_folder_id: u64,
+        name: &str,
+    ) -> Result<Option<u64>, Box<dyn std::error::Error + Send + Sync>> {
+        let sub_folder = client
+            .list_folder(parent_folder_id)?
+            .nofiles(true)
+            .get()
+            .await?
+            .metadata
+            .map(|m| m.contents)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|m| m.isfolder && m.name == name);
+
+        Ok(sub_folder.and_then(|m| m.folderid))
+    }
+
+    /// Moves the folder into the destination, merging it with or replacing an existing folder with the same name
+    async fn move_with_conflict_handling(
+        self,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let source = PCloudFolder {
+            folder_id: self.from_folder_id,
+            path: self.from_path.clone(),
+            parent_folder_id: None,
+            name: None,
+        };
+        let source = self
+            .client
+            .list_folder(source)?
+            .nofiles(true)
+            .get()
+            .await?
+            .metadata
+            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+        let source_id = source.folderid.ok_or(PCloudResult::DirectoryDoesNotExist)?;
+
+        let (parent_id, name) = self.resolve_destination(&source.name).await?;
+        let client = self.client;
+
+        let existing_id = match Self::find_sub_folder(&client, parent_id, &name).await? {
+            // Moving a folder onto itself is a no-op for pCloud
+            Some(existing_id) if existing_id != source_id => existing_id,
+            _ => return Self::move_into(&client, source_id, parent_id, &name).await,
+        };
+
+        if self.on_conflict == MoveConflict::Replace {
+            debug!(
+                "Replacing existing folder {} with folder {}",
+                existing_id, source_id
+            );
+            // Move the source next to the existing folder first, so the existing folder is kept if the move fails
+            let temp_name = format!(".{}.{}", name, Uuid::new_v4());
+            Self::move_into(&client, source_id, parent_id, &temp_name).await?;
+            client
+                .delete_folder(existing_id)?
+                .delete_recursive()
+                .await?;
+            return Self::move_into(&client, source_id, parent_id, &name).await;
+        }
+
+        // Merge without recursion: each entry is a source folder and the existing folder to merge it into
+        let mut pending = vec![(source_id, existing_id)];
+        // Source folders in the order they were visited: sub folders are always visited after their parent
+        let mut visited = Vec::new();
+        while let Some((from_id, into_id)) = pending.pop() {
+            debug!("Merging folder {} into folder {}", from_id, into_id);
+            visited.push(from_id);
+            let contents = client
+                .list_folder(from_id)?
+                .get()
+                .await?
+                .metadata
+                .map(|m| m.contents)
+                .unwrap_or_default();
+
+            for entry in contents {
+                if entry.isfolder {
+                    let sub_folder_id = entry.folderid.ok_or(PCloudResult::InvalidFolderId)?;
+                    match Self::find_sub_folder(&client, into_id, &entry.name).await? {
+                        Some(existing_sub_folder_id) => {
+                            pending.push((sub_folder_id, existing_sub_folder_id))
+                        }
+                        None => {
+                            Self::move_into(&client, sub_folder_id, into_id, &entry.name).await?;
+                        }
+                    }
+                } else {
+                    // Existing files with the same name are replaced by pCloud
+                    client.move_file(&entry, into_id)?.execute().await?;
+                }
+            }
+        }
+
+        // Remove the merged source folders bottom-up. Fails if anything was left behind (or added meanwhile) instead of deleting it
+        for folder_id in visited.into_iter().rev() {
+            client
+                .delete_folder(folder_id)?
+                .delete_folder_if_empty()
+                .await?;
+        }
+
+        let mut stat = client.list_folder(existing_id)?.nofiles(true).get().await?;
+        if let Some(metadata) = stat.metadata.as_mut() {
+            metadata.contents.clear();
+        }
+        Ok(stat)
+    }
+
+    /// Moves the folder with the given id into the given parent folder using the given name
+    async fn move_into(
+        client: &PCloudClient,
+        folder_id: u64,
+        to_folder_id: u64,
+        to_name: &str,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        MoveFolderRequestBuilder {
+            client: client.clone(),
+            from_path: None,
+            from_folder_id: Some(folder_id),
+            to_path: None,
+            to_folder_id: Some(to_folder_id),
+            to_name: Some(to_name.to_string()),
+            on_conflict: MoveConflict::Fail,
+        }
+        .rename_folder()
+        .await
+    }
+
+    /// Performs the actual renamefolder request
+    async fn rename_folder(
+        self,
     ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
         let mut r = self
             .client
             .client
             .post(format!("{}/renamefolder", self.client.api_host));
 
-        if let Some(v) = self.from_path {
+        if let Some(v) = &self.from_path {
             r = r.query(&[("path", v)]);
         }
 
@@ -542,14 +847,18 @@ impl MoveFolderRequestBuilder {
             r = r.query(&[("toname", v)]);
         }
 
-        r = self.client.add_token(r);
+        if self.client.is_dry_run() {
+            self.client.log_dry_run(r);
+            return Ok(pcloud_model::FileOrFolderStat::dry_run());
+        }
 
-        let result = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        self.client
+            .invalidate_cached_folder(self.from_folder_id, self.from_path.as_deref());
+
+        let result = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
         Ok(result)
     }
 }
@@ -569,14 +878,52 @@ pub struct ListFolderRequestBuilder {
     no_files: bool,
     /// If is set, only user's own folders and files will be displayed.
     no_shares: bool,
+    /// If set, only files of the given category are kept in the result (client-side filter)
+    category_filter: Option<FileCategory>,
+    /// If set, only entries whose name matches the glob pattern are kept in the result (client-side filter)
+    name_glob_filter: Option<String>,
+    /// If set, only shared-in or shared-out entries are kept in the result (client-side filter)
+    only_shared: bool,
+    /// If set, only entries owned by the user are kept in the result (client-side filter)
+    only_mine: bool,
+    /// If set, the tree is listed level by level up to the given depth (instead of recursive)
+    max_depth: Option<u32>,
+    /// Format of the returned icons
+    icon_format: Option<String>,
+    /// If set, the contents are sorted by this key (client-side)
+    sort_key: Option<SortKey>,
+    /// Direction of the sorting
+    sort_direction: SortDirection,
+    /// If set, folders are placed before files (client-side)
+    folders_first: bool,
+}
+
+/// Key to sort the contents of a folder listing by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Name of the file or folder (case-insensitive)
+    Name,
+    /// Size of the file, folders have no size and are treated as empty
+    Size,
+    /// Date of the last modification
+    Modified,
+    /// Date of the creation
+    Created,
+}
+
+/// Direction of the sorting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
 }
 
 #[allow(dead_code)]
 impl ListFolderRequestBuilder {
-    pub(crate) fn for_folder<'a, T: FolderDescriptor>(
+    pub(crate) fn for_folder<T: FolderDescriptor>(
         client: &PCloudClient,
         folder_like: T,
-    ) -> Result<ListFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<ListFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = folder_like.to_folder()?;
 
         if !f.is_empty() {
@@ -588,6 +935,15 @@ impl ListFolderRequestBuilder {
                 show_deleted: false,
                 no_files: false,
                 no_shares: false,
+                category_filter: None,
+                name_glob_filter: None,
+                only_shared: false,
+                only_mine: false,
+                max_depth: None,
+                icon_format: None,
+                sort_key: None,
+                sort_direction: SortDirection::Asc,
+                folders_first: false,
             })
         } else {
             Err(pcloud_model::PCloudResult::NoFileIdOrPathProvided)?
@@ -618,26 +974,233 @@ impl ListFolderRequestBuilder {
         self
     }
 
+    /// Only keep files of the given category in the result. pCloud has no server-side filter, so this only filters the already downloaded tree. If recursive is set, folders are kept as long as they contain matching entries.
+    pub fn filter_category(mut self, value: FileCategory) -> ListFolderRequestBuilder {
+        self.category_filter = Some(value);
+        self
+    }
+
+    /// Only keep entries whose name matches the given glob pattern (supports `*` and `?`). pCloud has no server-side filter, so this only filters the already downloaded tree. If recursive is set, folders are kept as long as they contain matching entries.
+    pub fn filter_name_glob(mut self, value: &str) -> ListFolderRequestBuilder {
+        self.name_glob_filter = Some(value.to_string());
+        self
+    }
+
+    /// Only keep entries shared with the user by others (shared-in) or shared by the user with others (shared-out). Filtered client-side, since pCloud returns everything in one listing. If recursive is set, folders are kept as long as they contain matching entries.
+    pub fn only_shared(mut self, value: bool) -> ListFolderRequestBuilder {
+        self.only_shared = value;
+        self
+    }
+
+    /// Only keep entries owned by the user. Unlike noshares, the shared-in folders are still listed by pCloud, but removed client-side afterwards.
+    pub fn only_mine(mut self, value: bool) -> ListFolderRequestBuilder {
+        self.only_mine = value;
+        self
+    }
+
+    /// Format of the returned icons (like `id` to get numeric icon ids). Icons not known to this client are returned as FileIcon::Other.
+    pub fn with_icon_format(mut self, value: &str) -> ListFolderRequestBuilder {
+        self.icon_format = Some(value.to_string());
+        self
+    }
+
+    /// Sorts the contents (of all levels) by the given key (client-side). Without a sort key the order is defined by pCloud.
+    pub fn sort_by(mut self, value: SortKey) -> ListFolderRequestBuilder {
+        self.sort_key = Some(value);
+        self
+    }
+
+    /// Direction of the sorting (defaults to ascending)
+    pub fn sort_dir(mut self, value: SortDirection) -> ListFolderRequestBuilder {
+        self.sort_direction = value;
+        self
+    }
+
+    /// If set, folders are placed before files (client-side). Can be combined with sort_by() to sort folders and files separately.
+    pub fn folders_first(mut self, value: bool) -> ListFolderRequestBuilder {
+        self.folders_first = value;
+        self
+    }
+
+    /// Lists the folder tree level by level (one request per folder) down to the given depth, instead of fetching the whole tree at once with recursive. Depth 1 only lists the direct contents of the folder, deeper folders are returned with empty contents. Overrides recursive. Useful to limit the traversal cost for huge, deeply nested folder structures.
+    pub fn max_depth(mut self, value: u32) -> ListFolderRequestBuilder {
+        self.max_depth = Some(value.max(1));
+        self
+    }
+
     /// Execute list operation
     pub async fn get(
         self,
     ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut stat = match self.max_depth {
+            Some(max_depth) => self.fetch_up_to_depth(max_depth).await?,
+            None => {
+                self.fetch(self.path.clone(), self.folder_id, self.recursive)
+                    .await?
+            }
+        };
+
+        if self.category_filter.is_some()
+            || self.name_glob_filter.is_some()
+            || self.only_shared
+            || self.only_mine
+        {
+            let filter = ListFolderFilter {
+                category: self.category_filter,
+                name_glob: self.name_glob_filter,
+                only_shared: self.only_shared,
+                only_mine: self.only_mine,
+                recursive: self.recursive || self.max_depth.is_some(),
+            };
+            if let Some(metadata) = stat.metadata.as_mut() {
+                let contents = std::mem::take(&mut metadata.contents);
+                metadata.contents = filter.apply(contents);
+            }
+        }
+
+        if self.sort_key.is_some() || self.folders_first {
+            let sort = ListFolderSort {
+                key: self.sort_key,
+                direction: self.sort_direction,
+                folders_first: self.folders_first,
+            };
+            if let Some(metadata) = stat.metadata.as_mut() {
+                sort.apply(&mut metadata.contents);
+            }
+        }
+
+        Ok(stat)
+    }
+
+    /// Fetches only the folder hierarchy (implies nofiles) and returns it as lean folder tree. Unless a max_depth is set, the whole hierarchy is listed recursively.
+    pub async fn get_folder_tree(
+        mut self,
+    ) -> Result<FolderNode, Box<dyn std::error::Error + Send + Sync>> {
+        self.no_files = true;
+        if self.max_depth.is_none() {
+            self.recursive = true;
+        }
+
+        let stat = self.get().await?;
+
+        match stat.metadata.as_ref().and_then(FolderNode::of) {
+            Some(tree) => Ok(tree),
+            None => Err(PCloudResult::DirectoryDoesNotExist)?,
+        }
+    }
+
+    /// Lists the folder and then each sub folder level by level, stitching the results into a single tree
+    async fn fetch_up_to_depth(
+        &self,
+        max_depth: u32,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let mut stat = self.fetch(self.path.clone(), self.folder_id, false).await?;
+
+        if let Some(root) = stat.metadata.as_mut() {
+            // Positions (as child indices starting from the root) of the folders of the current level
+            let mut level: Vec<Vec<usize>> = vec![Vec::new()];
+
+            for _ in 1..max_depth {
+                let mut next_level = Vec::new();
+
+                for position in level {
+                    let node = ListFolderRequestBuilder::node_at(root, &position);
+                    for (index, child) in node.contents.iter().enumerate() {
+                        if child.isfolder {
+                            let mut child_position = position.clone();
+                            child_position.push(index);
+                            next_level.push(child_position);
+                        }
+                    }
+                }
+
+                for position in &next_level {
+                    let node = ListFolderRequestBuilder::node_at(root, position);
+                    if let Some(folder_id) = node.folderid {
+                        let listing = self.fetch(None, Some(folder_id), false).await?;
+                        node.contents = listing.metadata.map(|m| m.contents).unwrap_or_default();
+                    }
+                }
+
+                if next_level.is_empty() {
+                    break;
+                }
+                level = next_level;
+            }
+        }
+
+        Ok(stat)
+    }
+
+    /// Navigates to the entry at the given position of the tree
+    fn node_at<'m>(root: &'m mut Metadata, position: &[usize]) -> &'m mut Metadata {
+        position
+            .iter()
+            .fold(root, |node, index| &mut node.contents[*index])
+    }
+
+    /// Returns only the number of direct entries of the folder (e.g. to show the size of a folder before rendering it). pCloud still sends the complete listing, but the entries are skipped while parsing instead of building their metadata. nofiles, noshares and showdeleted are respected, recursive, client-side filters and max_depth are not.
+    pub async fn count_only(self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let r = self.request(self.path.clone(), self.folder_id, false);
+
+        let count = self.client.execute_json::<FolderEntryCount>(r).await?;
+        Ok(count.metadata.map(|m| m.contents.len()).unwrap_or(0))
+    }
+
+    /// Lists the folder (recursively, if set) and streams its entries to the returned channel while the response is parsed, instead of buffering the whole response. Useful for huge recursive listings, which otherwise need the whole tree in memory at once.
+    /// The entries are sent flat (with empty contents), usually the entries of a folder are sent before the folder itself. Client-side filters, sorting and max_depth are not applied. Errors are sent as last entry of the channel.
+    #[cfg(feature = "streaming_listing")]
+    pub async fn stream(
+        self,
+    ) -> Result<
+        tokio::sync::mpsc::Receiver<crate::streaming_listing::ListingEntry>,
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        let r = self.request(self.path.clone(), self.folder_id, self.recursive);
+        let response = self.client.add_token(r).send().await?.error_for_status()?;
+
+        Ok(crate::streaming_listing::stream_listing(response))
+    }
+
+    /// Performs a single listfolder request
+    async fn fetch(
+        &self,
+        path: Option<String>,
+        folder_id: Option<u64>,
+        recursive: bool,
+    ) -> Result<pcloud_model::FileOrFolderStat, Box<dyn std::error::Error + Send + Sync>> {
+        let r = self.request(path, folder_id, recursive);
+
+        let stat = self
+            .client
+            .execute_json::<pcloud_model::FileOrFolderStat>(r)
+            .await?;
+        Ok(stat)
+    }
+
+    /// Builds a single listfolder request
+    fn request(
+        &self,
+        path: Option<String>,
+        folder_id: Option<u64>,
+        recursive: bool,
+    ) -> RequestBuilder {
         let mut r = self
             .client
             .client
             .get(format!("{}/listfolder", self.client.api_host));
 
-        if let Some(v) = self.path {
+        if let Some(v) = path {
             debug!("List folder {}", v);
             r = r.query(&[("path", v)]);
         }
 
-        if let Some(v) = self.folder_id {
+        if let Some(v) = folder_id {
             debug!("List folder {}", v);
             r = r.query(&[("folderid", v)]);
         }
 
-        if self.recursive {
+        if recursive {
             r = r.query(&[("recursive", "1")]);
         }
 
@@ -653,91 +1216,459 @@ impl ListFolderRequestBuilder {
             r = r.query(&[("noshares", "1")]);
         }
 
-        r = self.client.add_token(r);
+        if let Some(v) = &self.icon_format {
+            r = r.query(&[("iconformat", v)]);
+        }
 
-        let stat = r
-            .send()
-            .await?
-            .json::<pcloud_model::FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
-        Ok(stat)
+        r
+    }
+}
+
+/// Lean listfolder response, which only keeps the number of entries
+#[derive(Deserialize)]
+struct FolderEntryCount {
+    result: PCloudResult,
+    metadata: Option<FolderContentsCount>,
+}
+
+/// Contents of a listed folder, the entries are skipped while parsing
+#[derive(Deserialize)]
+struct FolderContentsCount {
+    #[serde(default)]
+    contents: Vec<IgnoredAny>,
+}
+
+impl WithPCloudResult for FolderEntryCount {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Client-side filter applied on the contents of a folder listing
+struct ListFolderFilter {
+    /// Category the files must have
+    category: Option<FileCategory>,
+    /// Glob pattern the names must match
+    name_glob: Option<String>,
+    /// Entries must be shared-in or shared-out
+    only_shared: bool,
+    /// Entries must be owned by the user
+    only_mine: bool,
+    /// Descend into sub folders
+    recursive: bool,
+}
+
+impl ListFolderFilter {
+    /// Checks if a single entry matches the filter
+    fn matches(&self, entry: &Metadata) -> bool {
+        if let Some(category) = &self.category {
+            if entry.isfolder || entry.category.as_ref() != Some(category) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.name_glob {
+            if !glob_match(pattern, &entry.name) {
+                return false;
+            }
+        }
+
+        if self.only_shared && !(entry.is_shared_in() || entry.is_shared_out()) {
+            return false;
+        }
+
+        if self.only_mine && !entry.ismine {
+            return false;
+        }
+
+        true
+    }
+
+    /// Prunes the given contents to the matching entries
+    fn apply(&self, contents: Vec<Metadata>) -> Vec<Metadata> {
+        contents
+            .into_iter()
+            .filter_map(|mut entry| {
+                if entry.isfolder && self.recursive {
+                    let children = std::mem::take(&mut entry.contents);
+                    entry.contents = self.apply(children);
+                    if !entry.contents.is_empty() || self.matches(&entry) {
+                        return Some(entry);
+                    }
+                    None
+                } else if self.matches(&entry) {
+                    Some(entry)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Client-side sorting of folder listings
+struct ListFolderSort {
+    /// Key to sort by
+    key: Option<SortKey>,
+    /// Direction of the sorting
+    direction: SortDirection,
+    /// Place folders before files
+    folders_first: bool,
+}
+
+impl ListFolderSort {
+    /// Compares two entries according to the sort key and direction
+    fn compare(&self, a: &Metadata, b: &Metadata) -> Ordering {
+        if self.folders_first && a.isfolder != b.isfolder {
+            // Folders first, regardless of the direction
+            return b.isfolder.cmp(&a.isfolder);
+        }
+
+        let ordering = match self.key {
+            Some(SortKey::Name) => a
+                .name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.name.cmp(&b.name)),
+            Some(SortKey::Size) => a.size.unwrap_or(0).cmp(&b.size.unwrap_or(0)),
+            Some(SortKey::Modified) => a.modified.cmp(&b.modified),
+            Some(SortKey::Created) => a.created.cmp(&b.created),
+            None => Ordering::Equal,
+        };
+
+        match self.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+
+    /// Sorts the given contents and the contents of all sub folders
+    fn apply(&self, contents: &mut Vec<Metadata>) {
+        contents.sort_by(|a, b| self.compare(a, b));
+        for entry in contents.iter_mut().filter(|e| e.isfolder) {
+            self.apply(&mut entry.contents);
+        }
+    }
+}
+
+/// Simple glob matching supporting `*` (any number of characters) and `?` (exactly one character)
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_v = 0;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_v = v;
+            p += 1;
+        } else if let Some(s) = star {
+            p = s + 1;
+            star_v += 1;
+            v = star_v;
+        } else {
+            return false;
+        }
     }
+
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+
+    p == pattern.len()
 }
 
 #[allow(dead_code)]
 impl PCloudClient {
     /// Lists the content of a folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
-    pub fn list_folder<'a, T: FolderDescriptor>(
+    pub fn list_folder<T: FolderDescriptor>(
         &self,
         folder_like: T,
-    ) -> Result<ListFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<ListFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         ListFolderRequestBuilder::for_folder(self, folder_like)
     }
 
     /// Creates a new folder in a parent folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
-    pub fn create_folder<'a, T: FolderDescriptor>(
+    pub fn create_folder<T: FolderDescriptor>(
         &self,
         parent_folder_like: T,
         name: &str,
-    ) -> Result<CreateFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CreateFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         CreateFolderRequestBuilder::for_folder(self, parent_folder_like, name)
     }
 
+    /// Creates all folders of the given path, which do not exist yet (like `mkdir -p`). pCloud has no recursive create, so each path component is created with a separate request. Returns the id and metadata of the last folder of the path.
+    pub async fn create_folders_recursive(
+        &self,
+        path: &str,
+    ) -> Result<(u64, Metadata), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(folder_id) = self.cached_id(PathKind::Folder, path) {
+            if let Ok(resolved) = self.resolve_folder(folder_id).await {
+                return Ok(resolved);
+            }
+        }
+
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        if components.is_empty() {
+            return self.resolve_folder(0).await;
+        }
+
+        let mut parent_folder_id: u64 = 0;
+        let mut current_path = String::new();
+        let mut current = None;
+
+        for name in components {
+            current_path = format!("{}/{}", current_path, name);
+
+            let metadata = self
+                .create_folder(parent_folder_id, name)?
+                .if_not_exists(true)
+                .execute()
+                .await?
+                .metadata
+                .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+            let folder_id = metadata.folderid.ok_or(PCloudResult::InvalidFolderId)?;
+            debug!(
+                "Ensured folder {} exists with id {}",
+                current_path, folder_id
+            );
+
+            self.cache_id(PathKind::Folder, &current_path, folder_id);
+            parent_folder_id = folder_id;
+            current = Some((folder_id, metadata));
+        }
+
+        // components is not empty, so at least one folder has been created
+        Ok(current.unwrap())
+    }
+
     /// Deletes a folder. Either only if empty or recursively. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
-    pub fn delete_folder<'a, T: FolderDescriptor>(
+    pub fn delete_folder<T: FolderDescriptor>(
         &self,
         folder_like: T,
-    ) -> Result<DeleteFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<DeleteFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         DeleteFolderRequestBuilder::for_folder(self, folder_like)
     }
 
     /// Copies a folder identified by folderid or path to either topath or tofolderid.
-    pub fn copy_folder<'a, S: FolderDescriptor, T: FolderDescriptor>(
+    pub fn copy_folder<S: FolderDescriptor, T: FolderDescriptor>(
         &self,
         folder_like: S,
         target_folder_like: T,
-    ) -> Result<CopyFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<CopyFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         CopyFolderRequestBuilder::copy_folder(self, folder_like, target_folder_like)
     }
 
     /// Renames (and/or moves) a folder identified by folderid or path to either topath (if topath is a existing folder to place source folder without new name for the folder it MUST end with slash - /newpath/) or tofolderid/toname (one or both can be provided).
-    pub fn move_folder<'a, S: FolderDescriptor, T: FolderDescriptor>(
+    pub fn move_folder<S: FolderDescriptor, T: FolderDescriptor>(
         &self,
         folder_like: S,
         target_folder_like: T,
-    ) -> Result<MoveFolderRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<MoveFolderRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         MoveFolderRequestBuilder::move_folder(self, folder_like, target_folder_like)
     }
 
-    /// Returns the folder id of a PCloudFolder. If the folder_id is given, just return it. If a path is given, fetch the metadata with the folder id.
-    pub(crate) async fn get_folder_id<T: FolderDescriptor>(
+    /// Computes the number of files and sub folders and the total size of all files within a folder. If include_deleted is set, deleted (but recoverable) entries are counted, too. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    /// The tree is never kept in memory as a whole: with the streaming_listing feature a single recursive listing is streamed, otherwise the folders are listed one by one (one request per folder). Dropping the returned future stops the traversal.
+    pub async fn folder_size<T: FolderDescriptor>(
         &self,
         folder_like: T,
-    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        include_deleted: bool,
+    ) -> Result<FolderSize, Box<dyn std::error::Error + Send + Sync>> {
+        let request = self.list_folder(folder_like)?.showdeleted(include_deleted);
+
+        #[cfg(feature = "streaming_listing")]
+        let result = {
+            let mut result = FolderSize::default();
+            let mut entries = request.recursive(true).stream().await?;
+            while let Some(entry) = entries.recv().await {
+                result.add(&entry?, include_deleted);
+            }
+            result
+        };
+
+        #[cfg(not(feature = "streaming_listing"))]
+        let result = {
+            let mut result = FolderSize::default();
+            let mut pending: Vec<u64> = Vec::new();
+            let mut folder = request
+                .get()
+                .await?
+                .metadata
+                .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+
+            loop {
+                for entry in &folder.contents {
+                    if result.add(entry, include_deleted) && entry.isfolder {
+                        pending.push(entry.folderid.ok_or(PCloudResult::DirectoryDoesNotExist)?);
+                    }
+                }
+
+                match pending.pop() {
+                    Some(folder_id) => {
+                        folder = self
+                            .list_folder(folder_id)?
+                            .showdeleted(include_deleted)
+                            .get()
+                            .await?
+                            .metadata
+                            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+                    }
+                    None => break,
+                }
+            }
+            result
+        };
+
+        debug!(
+            "Folder contains {} files and {} folders with {} bytes",
+            result.files, result.folders, result.total_bytes
+        );
+        Ok(result)
+    }
+
+    /// Finds files with identical contents within the folder and all its sub folders, grouped by the content hash. Only groups with more than one file are returned. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub async fn find_duplicates<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<HashMap<u64, Vec<Metadata>>, Box<dyn std::error::Error + Send + Sync>> {
+        let root = self
+            .list_folder(folder_like)?
+            .recursive(true)
+            .get()
+            .await?
+            .metadata
+            .ok_or(PCloudResult::DirectoryDoesNotExist)?;
+
+        let mut groups: HashMap<u64, Vec<Metadata>> = HashMap::new();
+        let mut pending = vec![root];
+
+        while let Some(mut entry) = pending.pop() {
+            if entry.isfolder {
+                pending.append(&mut entry.contents);
+            } else if let Some(hash) = entry.hash {
+                groups.entry(hash).or_default().push(entry);
+            }
+        }
+
+        groups.retain(|_, files| files.len() > 1);
+        debug!("Found {} groups of duplicate files", groups.len());
+        Ok(groups)
+    }
+
+    /// Checks if the given folder exists. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata). Only errors other than a missing folder are returned as error.
+    pub async fn folder_exists<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        match self.list_folder(folder_like)?.nofiles(true).get().await {
+            Ok(_) => Ok(true),
+            Err(e) => match e.downcast_ref::<PCloudResult>() {
+                Some(PCloudResult::DirectoryDoesNotExist)
+                | Some(PCloudResult::ComponentOfTheParentDirectoryDoesNotExist) => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Fetches the metadata of the parent folder of the given file or folder (without its contents), e.g. to build breadcrumbs. Returns None for the root folder, which has no parent.
+    pub async fn parent_of(
+        &self,
+        entry: &Metadata,
+    ) -> Result<Option<FileOrFolderStat>, Box<dyn std::error::Error + Send + Sync>> {
+        let parent_folder_id = match entry.parentfolderid {
+            Some(id) if entry.folderid != Some(0) => id,
+            _ => return Ok(None),
+        };
+
+        let mut stat = self
+            .list_folder(parent_folder_id)?
+            .nofiles(true)
+            .get()
+            .await?;
+
+        if let Some(metadata) = stat.metadata.as_mut() {
+            metadata.contents.clear();
+        }
+
+        Ok(Some(stat))
+    }
+
+    /// Resolves a folder to its id and its metadata (without contents) with a single request. Use this instead of get_folder_id if the metadata of the folder is needed afterwards anyway. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata).
+    pub async fn resolve_folder<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<(u64, Metadata), Box<dyn std::error::Error + Send + Sync>> {
         let folder = folder_like.to_folder()?;
 
-        if let Some(folder_id) = folder.folder_id {
-            Ok(folder_id)
+        let metadata = if let (None, Some(parent_folder_id), Some(name)) = (
+            folder.folder_id,
+            folder.parent_folder_id,
+            folder.name.as_ref(),
+        ) {
+            // No direct API to resolve a folder by parent id and name, so look it up in the parent folder
+            self.list_folder(parent_folder_id)?
+                .nofiles(true)
+                .get()
+                .await?
+                .metadata
+                .map(|m| m.contents)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|m| m.isfolder && &m.name == name)
+                .ok_or(PCloudResult::DirectoryDoesNotExist)?
         } else {
-            let metadata = self
+            let path = folder.path.clone();
+            let mut metadata = self
                 .list_folder(folder)?
                 .recursive(false)
                 .nofiles(true)
                 .get()
                 .await?
                 .metadata
-                .unwrap();
+                .ok_or(PCloudResult::DirectoryDoesNotExist)?;
 
-            if !metadata.isfolder {
-                Err(PCloudResult::InvalidFolderId)?
+            if let (Some(path), Some(folder_id)) = (path, metadata.folderid) {
+                self.cache_id(PathKind::Folder, &path, folder_id);
             }
+            metadata.contents.clear();
+            metadata
+        };
 
-            if let Some(folder_id) = metadata.folderid {
-                Ok(folder_id)
-            } else {
-                Err(PCloudResult::InvalidFolderId)?
-            }
+        if !metadata.isfolder {
+            Err(PCloudResult::InvalidFolderId)?
+        }
+
+        match metadata.folderid {
+            Some(folder_id) => Ok((folder_id, metadata)),
+            None => Err(PCloudResult::InvalidFolderId)?,
+        }
+    }
+
+    /// Returns the folder id of a PCloudFolder. If the folder_id is given (or the path is cached), just return it. Otherwise resolve the folder with its metadata.
+    pub(crate) async fn get_folder_id<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let folder = folder_like.to_folder()?;
+
+        if let Some(folder_id) = folder.folder_id {
+            Ok(folder_id)
+        } else if let Some(folder_id) = folder
+            .path
+            .as_ref()
+            .and_then(|path| self.cached_id(PathKind::Folder, path))
+        {
+            Ok(folder_id)
+        } else {
+            let (folder_id, _) = self.resolve_folder(folder).await?;
+            Ok(folder_id)
         }
     }
 }
@@ -1,7 +1,15 @@
+pub mod audio;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod directory_upload;
 pub mod events;
 pub mod file_ops;
+pub mod folder_merge;
 pub mod folder_ops;
 pub mod low_level_file;
+mod path_cache;
 pub mod pcloud_client;
 pub mod pcloud_model;
 pub mod remote_zip;
+pub mod streaming_listing;
+pub mod upload_links;
@@ -7,8 +7,8 @@ use reqwest::Body;
 use crate::{
     file_ops::{FileDescriptor, PCloudFile},
     folder_ops::FolderDescriptor,
-    pcloud_client::PCloudClient,
-    pcloud_model::{FileCloseResponse, FileOpenResponse, FileWriteResponse, WithPCloudResult},
+    pcloud_client::{run_cleanup, PCloudClient},
+    pcloud_model::{FileCloseResponse, FileOpenResponse, FileWriteResponse},
 };
 
 impl PCloudClient {
@@ -20,6 +20,8 @@ impl PCloudClient {
 
 #[derive(Eq, Hash, PartialEq)]
 pub enum PCloudFileFlag {
+    /// O_RDONLY: Open the file for reading only. This is the default, if no other flag is given.
+    READ = 0x0000,
     /// You do not need to specify O_WRITE even if you intend to write to the file. However that will preform write access control and quota checking and you will get possible errors during open, not at the first write.
     WRITE = 0x0002,
     /// If O_CREAT is set, file_open will create the file. In this case full "path" or "folderid" and "name" MUST be provided for the new file. If the file already exists the old file will be open unless O_EXCL is set, in which case open will fail.
@@ -34,6 +36,7 @@ pub enum PCloudFileFlag {
 impl PCloudFileFlag {
     fn to_number(&self) -> u16 {
         match self {
+            PCloudFileFlag::READ => 0x0000,
             PCloudFileFlag::WRITE => 0x0002,
             PCloudFileFlag::CREATE => 0x0040,
             PCloudFileFlag::EXCL => 0x0080,
@@ -57,12 +60,12 @@ impl InitiatePCloudFileOpenRequest {
     }
 
     /// Opens the file by its file id
-    pub async fn by_file_id<'a, T: FileDescriptor>(
+    pub async fn by_file_id<T: FileDescriptor>(
         self,
         file_like: T,
-    ) -> Result<PCloudFileOpenRequest, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<PCloudFileOpenRequest, Box<dyn std::error::Error + Send + Sync>> {
         let file = file_like.to_file()?;
-        let file_id = self.client.get_file_id(file).await?;
+        let (file_id, _) = self.client.get_file_id(file).await?;
 
         Ok(PCloudFileOpenRequest {
             client: self.client,
@@ -87,11 +90,11 @@ impl InitiatePCloudFileOpenRequest {
     }
 
     /// Target folder and file name of the target  file
-    pub async fn by_file_in_folder<'a, T: FolderDescriptor>(
+    pub async fn by_file_in_folder<T: FolderDescriptor>(
         self,
         folder_like: T,
         file_name: &str,
-    ) -> Result<PCloudFileOpenRequest, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<PCloudFileOpenRequest, Box<dyn std::error::Error + Send + Sync>> {
         let folder = folder_like.to_folder()?;
         let folder_id = self.client.get_folder_id(folder).await?;
 
@@ -128,6 +131,13 @@ impl PCloudFileOpenRequest {
         self
     }
 
+    /// Opens the file for reading only (e.g. for file_pread). Removes all previously added flags.
+    pub fn read_only(mut self) -> PCloudFileOpenRequest {
+        self.flags.clear();
+        self.flags.insert(PCloudFileFlag::READ);
+        self
+    }
+
     /// Performs the request to open the file
     pub async fn open(self) -> Result<OpenPCloudFile, Box<dyn std::error::Error + Send + Sync>> {
         let mut r = self
@@ -155,14 +165,7 @@ impl PCloudFileOpenRequest {
             r = r.query(&[("name", v)]);
         }
 
-        r = self.client.add_token(r);
-
-        let response = r
-            .send()
-            .await?
-            .json::<FileOpenResponse>()
-            .await?
-            .assert_ok()?;
+        let response = self.client.execute_json::<FileOpenResponse>(r).await?;
 
         let result = OpenPCloudFile {
             client: self.client,
@@ -198,25 +201,18 @@ impl OpenPCloudFile {
 
         r = r.query(&[("fd", fd)]);
 
-        r = client.add_token(r);
-
-        let result = r
-            .send()
-            .await?
-            .json::<FileCloseResponse>()
-            .await?
-            .assert_ok()?;
+        let result = client.execute_json::<FileCloseResponse>(r).await?;
 
         Ok(result)
     }
 
-    /// Close this file (Called by drop)
-    async fn close(
+    /// Closes this file and returns the result of the close operation. This is the recommended way to release the file descriptor, since the result can be observed. If not called, drop tries to close the file, but can only log failures.
+    pub async fn close(
         mut self,
     ) -> Result<FileCloseResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let result = Self::close_file(&self.client, self.fd).await?;
+        // Drop must not try to close the file again, even if closing fails
         self.open = false;
-        Ok(result)
+        Self::close_file(&self.client, self.fd).await
     }
 
     /// Write content to file
@@ -230,18 +226,13 @@ impl OpenPCloudFile {
             .post(format!("{}/file_write", self.client.api_host));
         r = r.query(&[("fd", self.fd)]);
 
-        r = self.client.add_token(r);
-
         let part = reqwest::multipart::Part::stream(body);
         let form = reqwest::multipart::Form::new().part("files", part);
 
-        let result = r
-            .multipart(form)
-            .send()
-            .await?
-            .json::<FileWriteResponse>()
-            .await?
-            .assert_ok()?;
+        let result = self
+            .client
+            .execute_json::<FileWriteResponse>(r.multipart(form))
+            .await?;
 
         Ok(result)
     }
@@ -254,7 +245,7 @@ impl Drop for OpenPCloudFile {
             let fd = self.fd.clone();
             let file_id = self.file_id.clone();
 
-            let op = tokio::spawn(async move {
+            run_cleanup(async move {
                 match Self::close_file(&client, fd).await {
                     Ok(_) => {
                         debug!("Successfully closed file with id {}", file_id);
@@ -264,8 +255,6 @@ impl Drop for OpenPCloudFile {
                     }
                 };
             });
-            // Wait until the lockout thread finished
-            futures::executor::block_on(op).unwrap();
         }
     }
 }
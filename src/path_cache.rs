@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use crate::pcloud_client::PCloudClient;
+
+/// Kind of object a cached path resolves to. Files and folders are cached separately, since the same path can never be both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PathKind {
+    File,
+    Folder,
+}
+
+/// Least-recently-used cache of resolved path -> id mappings. Used to skip redundant stat / listfolder calls when the same paths are used over and over again.
+#[derive(Debug)]
+pub(crate) struct PathCache {
+    /// Maximum number of entries kept
+    capacity: usize,
+    /// Cached ids together with the tick of their last use
+    entries: HashMap<(PathKind, String), (u64, u64)>,
+    /// Monotonic counter to determine the least recently used entry
+    tick: u64,
+}
+
+impl PathCache {
+    pub(crate) fn new(capacity: usize) -> PathCache {
+        PathCache {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            tick: 0,
+        }
+    }
+
+    /// Paths are compared without trailing slashes, since pCloud treats /a/b and /a/b/ the same
+    fn normalize(path: &str) -> String {
+        let trimmed = path.trim_end_matches('/');
+        if trimmed.is_empty() {
+            "/".to_string()
+        } else {
+            trimmed.to_string()
+        }
+    }
+
+    /// Returns the cached id for the path (if any) and marks it as recently used
+    pub(crate) fn get(&mut self, kind: PathKind, path: &str) -> Option<u64> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries
+            .get_mut(&(kind, PathCache::normalize(path)))
+            .map(|(id, last_used)| {
+                *last_used = tick;
+                *id
+            })
+    }
+
+    /// Caches the id of the given path. Evicts the least recently used entry if the cache is full.
+    pub(crate) fn insert(&mut self, kind: PathKind, path: &str, id: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        let key = (kind, PathCache::normalize(path));
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (id, self.tick));
+    }
+
+    /// Removes the cached file path and all cached entries pointing to the given file id
+    pub(crate) fn invalidate_file(&mut self, file_id: Option<u64>, path: Option<&str>) {
+        if let Some(path) = path {
+            self.entries
+                .remove(&(PathKind::File, PathCache::normalize(path)));
+        }
+        if let Some(file_id) = file_id {
+            self.entries
+                .retain(|(kind, _), (id, _)| !(*kind == PathKind::File && *id == file_id));
+        }
+    }
+
+    /// Removes the cached folder path and everything cached below it. If only the folder id is known and its path is not cached, the whole cache is cleared, since cached children can not be identified otherwise.
+    pub(crate) fn invalidate_folder(&mut self, folder_id: Option<u64>, path: Option<&str>) {
+        let mut prefixes: Vec<String> = Vec::new();
+
+        if let Some(path) = path {
+            prefixes.push(PathCache::normalize(path));
+        }
+
+        if let Some(folder_id) = folder_id {
+            let known: Vec<String> = self
+                .entries
+                .iter()
+                .filter(|((kind, _), (id, _))| *kind == PathKind::Folder && *id == folder_id)
+                .map(|((_, path), _)| path.clone())
+                .collect();
+            if known.is_empty() && path.is_none() {
+                self.entries.clear();
+                return;
+            }
+            prefixes.extend(known);
+        }
+
+        for prefix in prefixes {
+            if prefix == "/" {
+                self.entries.clear();
+                return;
+            }
+            let children = format!("{}/", prefix);
+            self.entries
+                .retain(|(_, path), _| path != &prefix && !path.starts_with(&children));
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PCloudClient {
+    /// Returns the cached id for the given path, if the path cache is enabled and contains the path
+    pub(crate) fn cached_id(&self, kind: PathKind, path: &str) -> Option<u64> {
+        self.path_cache
+            .as_ref()
+            .and_then(|cache| cache.lock().unwrap().get(kind, path))
+    }
+
+    /// Caches the resolved id of the path, if the path cache is enabled
+    pub(crate) fn cache_id(&self, kind: PathKind, path: &str, id: u64) {
+        if let Some(cache) = &self.path_cache {
+            cache.lock().unwrap().insert(kind, path, id);
+        }
+    }
+
+    /// Drops all cached paths related to the given file
+    pub(crate) fn invalidate_cached_file(&self, file_id: Option<u64>, path: Option<&str>) {
+        if let Some(cache) = &self.path_cache {
+            cache.lock().unwrap().invalidate_file(file_id, path);
+        }
+    }
+
+    /// Drops all cached paths related to the given folder (including all paths below it)
+    pub(crate) fn invalidate_cached_folder(&self, folder_id: Option<u64>, path: Option<&str>) {
+        if let Some(cache) = &self.path_cache {
+            cache.lock().unwrap().invalidate_folder(folder_id, path);
+        }
+    }
+
+    /// Returns the cached file id of the given path, if the path cache is enabled and contains the path
+    pub fn cached_file_id(&self, path: &str) -> Option<u64> {
+        self.cached_id(PathKind::File, path)
+    }
+
+    /// Returns the cached folder id of the given path, if the path cache is enabled and contains the path
+    pub fn cached_folder_id(&self, path: &str) -> Option<u64> {
+        self.cached_id(PathKind::Folder, path)
+    }
+}
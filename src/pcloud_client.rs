@@ -1,25 +1,119 @@
-use crate::pcloud_model::{self, PCloudResult, UserInfo, WithPCloudResult};
-use log::{debug, warn};
+use crate::path_cache::PathCache;
+use crate::pcloud_model::{
+    self, ApiServers, ClientIp, CurrentServer, LogoutResponse, PCloudResult, SetLanguageResponse,
+    SupportedLanguages, UserInfo, WithPCloudResult,
+};
+use futures::{stream, Future, StreamExt};
+use log::{debug, info, warn};
 use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
 
 #[derive(Clone)]
 pub struct PCloudClient {
-    pub(crate) api_host: String,
+    /// Host of the pCloud API. Common for all copies of this PCloudClient
+    pub(crate) api_host: ApiHost,
     pub(crate) client: reqwest::Client,
-    /// Session auth token (not the OAuth2 token, which is set as default header). Common for all copies of this PCloudClient
+    /// Session auth token (not the OAuth2 token, see below). Common for all copies of this PCloudClient
     session_token: std::sync::Arc<Option<PCloudClientSession>>,
+    /// Optional cache of resolved path -> id mappings. Common for all copies of this PCloudClient
+    pub(crate) path_cache: Option<std::sync::Arc<std::sync::Mutex<PathCache>>>,
+    /// If set, destructive operations are only logged but not executed. Common for all copies of this PCloudClient
+    dry_run: std::sync::Arc<AtomicBool>,
+    /// Authorization header carrying the OAuth2 token (only for OAuth clients). Common for all copies of this PCloudClient
+    oauth_token: Option<std::sync::Arc<reqwest::header::HeaderValue>>,
+    /// If set, this auth token is used instead of the session token or the OAuth2 token (see with_auth_token())
+    auth_token: Option<std::sync::Arc<String>>,
+    /// If set, all outgoing requests are logged (without the auth token)
+    request_logging: bool,
+    /// If set, this User-Agent is sent with all requests instead of the default one of reqwest
+    user_agent: Option<std::sync::Arc<String>>,
+}
+
+/// Runs a cleanup operation from a synchronous context (like drop). Depending on the tokio runtime available:
+/// - multi-threaded runtime: the operation is awaited within block_in_place(), so the worker thread hands over its other tasks instead of being blocked
+/// - current-thread runtime: the operation is spawned, but not awaited, since blocking the only thread would dead-lock. It runs as soon as the runtime gets control again.
+/// - no runtime: the operation runs on a dedicated runtime on its own thread, which is awaited
+pub(crate) fn run_cleanup<F>(op: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(op));
+        }
+        Ok(handle) => {
+            warn!("Current-thread runtime detected, cleanup runs in the background and is lost if the runtime shuts down before");
+            handle.spawn(op);
+        }
+        Err(_) => {
+            debug!("No tokio runtime available, using a dedicated runtime for cleanup");
+            let cleanup = std::thread::spawn(move || {
+                match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime.block_on(op),
+                    Err(e) => warn!("Failed to create runtime for cleanup: {}", e),
+                }
+            });
+            if cleanup.join().is_err() {
+                warn!("Cleanup thread panicked");
+            }
+        }
+    }
+}
+
+/// Host of the pCloud API, shared between all copies of a PCloudClient so it can be swapped at runtime (see refresh_api_server())
+#[derive(Clone, Debug)]
+pub(crate) struct ApiHost {
+    /// Default host given on creation (either api.pcloud.com or eapi.pcloud.com)
+    default_host: std::sync::Arc<String>,
+    /// Host currently used for all requests
+    current_host: std::sync::Arc<std::sync::RwLock<String>>,
+}
+
+impl ApiHost {
+    fn new(default_host: &str, current_host: String) -> ApiHost {
+        ApiHost {
+            default_host: std::sync::Arc::new(default_host.to_string()),
+            current_host: std::sync::Arc::new(std::sync::RwLock::new(current_host)),
+        }
+    }
+
+    /// Returns the host currently used
+    fn get(&self) -> String {
+        self.current_host.read().unwrap().clone()
+    }
+
+    /// Replaces the host used for all further requests
+    fn set(&self, host: String) {
+        *self.current_host.write().unwrap() = host;
+    }
+}
+
+/// Allows to directly use the host in format!()
+impl Display for ApiHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.current_host.read().unwrap())
+    }
 }
 
 /// Contains the client session opened on login (not necessary for oauth2 sessions)
 /// Due to drop implementation, logout automatically happens once the sessions drops
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 struct PCloudClientSession {
-    /// Auth token (not the OAuth2 token, which is set as default header)
+    /// Auth token (not the OAuth2 token, which is sent as Authorization header)
     token: String,
     /// Host to connect to pCloud API
     api_host: String,
     /// Client to connect
     client: reqwest::Client,
+    /// Set once the session was logged out explicitly, so drop does not logout again
+    logged_out: AtomicBool,
 }
 
 impl PCloudClientSession {
@@ -34,70 +128,162 @@ impl PCloudClientSession {
 impl Drop for PCloudClientSession {
     /// Drop the aquired session token
     fn drop(&mut self) {
+        if self.logged_out.load(Ordering::SeqCst) {
+            return;
+        }
+
         let client = self.client.clone();
         let api_host = self.api_host.clone();
         let token = self.token.clone();
 
-        let op = tokio::spawn(async move {
-            let result = PCloudClient::logout(&client, &api_host, &token).await;
+        run_cleanup(async move {
+            let result = PCloudClient::revoke_token(&client, &api_host, &token).await;
 
             match result {
                 Ok(v) => {
-                    if v {
+                    if v.auth_deleted.unwrap_or(false) {
                         debug!("Successful logout");
                     } else {
                         warn!("Failed to logout");
                     }
-                    return v;
                 }
                 Err(_) => {
                     warn!("Error on logout");
-                    return false;
                 }
             }
         });
-        // Wait until the lockout thread finished
-        futures::executor::block_on(op).unwrap();
     }
 }
 
+/// Collects the options to create a PCloudClient (see PCloudClient::builder()). The client is created by one of the terminal methods login_with_oauth(), login_with_password() or anonymous().
+#[derive(Clone, Debug, Default)]
+pub struct PCloudClientBuilder {
+    /// User-Agent sent with all requests, including the ones made during the creation of the client
+    user_agent: Option<String>,
+    /// Timeout for all requests
+    timeout: Option<Duration>,
+    /// Capacity of the path cache, if enabled
+    path_cache_capacity: Option<usize>,
+    /// If set, all outgoing requests are logged (without the auth token)
+    request_logging: bool,
+    /// If set, destructive operations are only logged but not executed
+    dry_run: bool,
+    /// If set, compressed responses (gzip, deflate, brotli) are requested and decompressed transparently
+    accept_compressed: bool,
+}
+
 #[allow(dead_code)]
-impl PCloudClient {
-    /// Creates a new PCloudClient instance with an already present OAuth 2.0 authentication token. Automatically determines nearest API server for best performance
-    pub async fn with_oauth(
+impl PCloudClientBuilder {
+    /// Sends the given User-Agent with all requests, so apps can identify themselves. In contrast to PCloudClient::with_user_agent() this also covers the login and API server requests made during the creation of the client.
+    pub fn user_agent(mut self, value: &str) -> PCloudClientBuilder {
+        self.user_agent = Some(value.to_string());
+        self
+    }
+
+    /// Timeout for each request, from connecting until the response body is finished. Requests with an explicit timeout of their own (like the long-polling of diff with block_timeout()) are not affected. Keep in mind that large up- and downloads might take longer!
+    pub fn timeout(mut self, value: Duration) -> PCloudClientBuilder {
+        self.timeout = Some(value);
+        self
+    }
+
+    /// Enables caching of resolved path -> id mappings with the given capacity (see PCloudClient::with_path_cache())
+    pub fn path_cache(mut self, capacity: usize) -> PCloudClientBuilder {
+        self.path_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Enables debug logging of all outgoing requests (see PCloudClient::with_request_logging())
+    pub fn request_logging(mut self, value: bool) -> PCloudClientBuilder {
+        self.request_logging = value;
+        self
+    }
+
+    /// Starts the client in dry-run mode (see PCloudClient::dry_run())
+    pub fn dry_run(mut self, value: bool) -> PCloudClientBuilder {
+        self.dry_run = value;
+        self
+    }
+
+    /// Requests compressed responses (gzip, deflate or brotli) and decompresses them transparently, which considerably reduces the transfer size of large JSON responses (like recursive folder listings). Disabled by default: decompressed responses have no known content length anymore, so the progress of downloads served compressed could not be reported.
+    pub fn accept_compressed(mut self, value: bool) -> PCloudClientBuilder {
+        self.accept_compressed = value;
+        self
+    }
+
+    /// Creates the builder for the internal http client with the configured options applied
+    fn http_client_builder(&self) -> reqwest::ClientBuilder {
+        let mut builder = PCloudClient::client_builder()
+            .gzip(self.accept_compressed)
+            .deflate(self.accept_compressed)
+            .brotli(self.accept_compressed);
+
+        if let Some(ref v) = self.user_agent {
+            builder = builder.user_agent(v.clone());
+        }
+
+        if let Some(v) = self.timeout {
+            builder = builder.timeout(v);
+        }
+
+        builder
+    }
+
+    /// Assembles the client from the configured options
+    fn build(
+        self,
+        host: &str,
+        best_host: String,
+        client: reqwest::Client,
+        session: Option<PCloudClientSession>,
+    ) -> PCloudClient {
+        let client = PCloudClient {
+            api_host: ApiHost::new(host, best_host),
+            client: client,
+            session_token: std::sync::Arc::new(session),
+            oauth_token: None,
+            path_cache: None,
+            dry_run: std::sync::Arc::new(AtomicBool::new(self.dry_run)),
+            auth_token: None,
+            request_logging: self.request_logging,
+            user_agent: None,
+        };
+
+        match self.path_cache_capacity {
+            Some(capacity) => client.with_path_cache(capacity),
+            None => client,
+        }
+    }
+
+    /// Creates the client with an already present OAuth 2.0 authentication token. Automatically determines nearest API server for best performance
+    pub async fn login_with_oauth(
+        self,
         host: &str,
         oauth2: &str,
     ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
-        let builder = reqwest::ClientBuilder::new();
-
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            reqwest::header::HeaderValue::from_str(format!("Bearer {}", oauth2).as_str()).unwrap(),
-        );
+        let mut header =
+            reqwest::header::HeaderValue::from_str(format!("Bearer {}", oauth2).as_str())?;
+        header.set_sensitive(true);
 
-        let client = builder.default_headers(headers).build().unwrap();
+        let client = self.http_client_builder().build()?;
 
         let best_host = PCloudClient::get_best_api_server(&client, host, None).await?;
 
-        Ok(PCloudClient {
-            api_host: best_host,
-            client: client,
-            session_token: std::sync::Arc::new(None),
-        })
+        // Not set as default header of the http client, so it can be replaced by with_auth_token()
+        let mut client = self.build(host, best_host, client, None);
+        client.oauth_token = Some(std::sync::Arc::new(header));
+        Ok(client)
     }
 
-    /// Creates a new PCloudClient instance using username and password to obtain a temporary auth token. Token is shared between all clones of this instance and revoked when the last instance is dropped. Automatically determines nearest API server for best performance.
-    pub async fn with_username_and_password(
+    /// Creates the client using username and password to obtain a temporary auth token. Token is shared between all clones of the client and revoked when the last instance is dropped. Automatically determines nearest API server for best performance.
+    pub async fn login_with_password(
+        self,
         host: &str,
         username: &str,
         password: &str,
     ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
-        let token = PCloudClient::login(host, username, password).await?;
+        let client = self.http_client_builder().build()?;
 
-        let builder = reqwest::ClientBuilder::new();
-
-        let client = builder.build().unwrap();
+        let token = PCloudClient::login(&client, host, username, password).await?;
 
         let best_host =
             PCloudClient::get_best_api_server(&client, host, Some(token.clone())).await?;
@@ -106,25 +292,212 @@ impl PCloudClient {
             api_host: best_host.clone(),
             client: client.clone(),
             token: token,
+            logged_out: AtomicBool::new(false),
         };
 
-        Ok(PCloudClient {
-            api_host: best_host,
-            client: client,
-            session_token: std::sync::Arc::new(Some(session)),
-        })
+        Ok(self.build(host, best_host, client, Some(session)))
+    }
+
+    /// Creates the client without any authentication. Such a client can only be used to access public resources, like public file links. Automatically determines nearest API server for best performance.
+    pub async fn anonymous(
+        self,
+        host: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        let client = self.http_client_builder().build()?;
+
+        let best_host = PCloudClient::get_best_api_server(&client, host, None).await?;
+
+        Ok(self.build(host, best_host, client, None))
+    }
+}
+
+#[allow(dead_code)]
+impl PCloudClient {
+    /// Creates a builder to configure the client (User-Agent, timeout, path cache, ...) before logging in. The constructors below are shortcuts for a builder with default options.
+    pub fn builder() -> PCloudClientBuilder {
+        PCloudClientBuilder::default()
+    }
+
+    /// Creates a new PCloudClient instance with an already present OAuth 2.0 authentication token. Automatically determines nearest API server for best performance
+    pub async fn with_oauth(
+        host: &str,
+        oauth2: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        PCloudClient::builder().login_with_oauth(host, oauth2).await
+    }
+
+    /// Creates a new PCloudClient instance using username and password to obtain a temporary auth token. Token is shared between all clones of this instance and revoked when the last instance is dropped. Automatically determines nearest API server for best performance.
+    pub async fn with_username_and_password(
+        host: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        PCloudClient::builder()
+            .login_with_password(host, username, password)
+            .await
+    }
+
+    /// Creates a new PCloudClient instance without any authentication. Such a client can only be used to access public resources, like public file links. Automatically determines nearest API server for best performance.
+    pub async fn anonymous(
+        host: &str,
+    ) -> Result<PCloudClient, Box<dyn std::error::Error + Send + Sync>> {
+        PCloudClient::builder().anonymous(host).await
+    }
+
+    /// Enables caching of resolved path -> id mappings with the given capacity (least recently used entries are evicted first). Repeatedly used paths are then only resolved once. Cached entries are invalidated on move, rename and delete operations performed by this client (or any of its copies), changes made by other clients are not noticed!
+    pub fn with_path_cache(mut self, capacity: usize) -> PCloudClient {
+        self.path_cache = Some(std::sync::Arc::new(std::sync::Mutex::new(PathCache::new(
+            capacity,
+        ))));
+        self
+    }
+
+    /// Returns a copy of this client, which sends the given auth token with all its requests instead of the session token. Useful for scoped tokens issued for single operations (e.g. for business sub-accounts). Only requests created from the returned copy are affected. Neither the session token nor the OAuth token (Authorization header) are sent by the returned copy, so pCloud only sees the given token.
+    pub fn with_auth_token(&self, token: &str) -> PCloudClient {
+        let mut client = self.clone();
+        client.auth_token = Some(std::sync::Arc::new(token.to_string()));
+        client
+    }
+
+    /// Enables or disables the dry-run mode for this client and all its copies. In dry-run mode destructive operations (deleting and moving files and folders) are not sent to pCloud, but the intended request is logged and a synthesized successful response is returned. Useful to validate cleanup scripts safely.
+    pub fn dry_run(&self, value: bool) {
+        self.dry_run.store(value, Ordering::SeqCst);
+    }
+
+    /// Checks if the dry-run mode is enabled
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::SeqCst)
+    }
+
+    /// Enables debug logging of all outgoing requests (method and url). The auth token is redacted, so the logs are safe to share.
+    pub fn with_request_logging(mut self, value: bool) -> PCloudClient {
+        self.request_logging = value;
+        self
+    }
+
+    /// Sends the given User-Agent with all further requests of this client (and its copies made afterwards), so apps can identify themselves. The requests made during the creation of the client still use the default User-Agent of reqwest, use PCloudClientBuilder::user_agent() to cover them as well.
+    pub fn with_user_agent(mut self, value: &str) -> PCloudClient {
+        self.user_agent = Some(std::sync::Arc::new(value.to_string()));
+        self
+    }
+
+    /// Logs the given request instead of sending it (used in dry-run mode). The session token is not logged.
+    pub(crate) fn log_dry_run(&self, r: RequestBuilder) {
+        match r.build() {
+            Ok(request) => {
+                info!(
+                    "Dry run, skipping request {} {}",
+                    request.method(),
+                    PCloudClient::redacted_url(&request)
+                );
+            }
+            Err(e) => warn!("Dry run, failed to build request: {}", e),
+        }
+    }
+
+    /// Logs the given request, if request logging is enabled. Requests with streamed bodies can not be inspected and are skipped.
+    fn log_request(&self, r: &RequestBuilder) {
+        if !self.request_logging {
+            return;
+        }
+
+        if let Some(Ok(request)) = r.try_clone().map(|r| r.build()) {
+            debug!(
+                "Request {} {}",
+                request.method(),
+                PCloudClient::redacted_url(&request)
+            );
+        }
+    }
+
+    /// Url of the request without the auth token
+    fn redacted_url(request: &reqwest::Request) -> reqwest::Url {
+        let mut url = request.url().clone();
+        let query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| {
+                if k == "auth" {
+                    (k.into_owned(), String::from("REDACTED"))
+                } else {
+                    (k.into_owned(), v.into_owned())
+                }
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(query);
+        url
+    }
+
+    /// Returns the API host currently used for all requests
+    pub fn api_host(&self) -> String {
+        self.api_host.get()
+    }
+
+    /// Determines the best API server again and uses it for all further requests (of this client and all its copies). Useful for long-running processes, if the server chosen on creation degrades. Returns the new API host.
+    pub async fn refresh_api_server(
+        &self,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let token = self
+            .session_token
+            .as_ref()
+            .as_ref()
+            .map(|s| s.token.clone());
+
+        let best_host =
+            PCloudClient::get_best_api_server(&self.client, &self.api_host.default_host, token)
+                .await?;
+
+        debug!("Switching pCloud API endpoint to {}", best_host);
+        self.api_host.set(best_host.clone());
+        Ok(best_host)
+    }
+
+    /// Runs the given tasks in parallel (at most max_concurrency at once), each on its own tokio task with its own copy of this client, and returns their results in the order of the tasks.
+    /// All high-level operations (like uploads, downloads, listings) are independent HTTP requests and can safely run concurrently on copies of the same client, which share the session and the connection pool.
+    /// This does not hold for low-level file operations: file descriptors are only valid on the connection they were opened on, so an open file must never be shared between tasks.
+    pub async fn spawn_concurrent<T, F, Fut>(
+        &self,
+        tasks: impl IntoIterator<Item = F>,
+        max_concurrency: usize,
+    ) -> Vec<Result<T, Box<dyn std::error::Error + Send + Sync>>>
+    where
+        F: FnOnce(PCloudClient) -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static,
+        T: Send + 'static,
+    {
+        // Tasks are only spawned once a slot is free, so at most max_concurrency tasks run at once
+        stream::iter(tasks)
+            .map(|task| tokio::spawn(task(self.clone())))
+            .buffered(max_concurrency.max(1))
+            .map(|joined| match joined {
+                Ok(result) => result,
+                Err(e) => Err(e)?,
+            })
+            .collect()
+            .await
+    }
+
+    /// Creates the builder for the internal http client, configured with the TLS implementation chosen by the crate features (rustls-tls takes precedence over native-tls)
+    fn client_builder() -> reqwest::ClientBuilder {
+        let builder = reqwest::ClientBuilder::new();
+
+        #[cfg(feature = "rustls-tls")]
+        let builder = builder.use_rustls_tls();
+
+        #[cfg(all(feature = "native-tls", not(feature = "rustls-tls")))]
+        let builder = builder.use_native_tls();
+
+        builder
     }
 
     /// Performs the login to pCloud using username and password.
     async fn login(
+        client: &Client,
         host: &str,
         username: &str,
         password: &str,
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!("{}/userinfo?getauth=1", host);
 
-        let client = reqwest::ClientBuilder::new().build()?;
-
         let mut r = client.get(url);
 
         r = r.query(&[("username", username)]);
@@ -141,11 +514,11 @@ impl PCloudClient {
     }
 
     /// Performs the logout for the token aquired with login
-    async fn logout(
+    async fn revoke_token(
         client: &Client,
         api_host: &str,
         token: &str,
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<LogoutResponse, Box<dyn std::error::Error + Send + Sync>> {
         let mut r = client.get(format!("{}/logout", api_host));
 
         r = r.query(&[("auth", token)]);
@@ -154,15 +527,66 @@ impl PCloudClient {
             .send()
             .await?
             .json::<pcloud_model::LogoutResponse>()
-            .await?;
+            .await?
+            .assert_ok()?;
 
-        Ok(response.result == PCloudResult::Ok
-            && response.auth_deleted.is_some()
-            && response.auth_deleted.unwrap())
+        Ok(response)
     }
 
-    /// If theres is a session token present, add it to the given request.
+    /// Explicitly revokes the session token instead of waiting for the last copy of this client to be dropped. The session is shared, so all copies of this client are logged out! Calling logout more than once (or on a copy) is fine, only the first call revokes the token. Clients without session (OAuth or anonymous clients) and already logged out clients return a successful result with auth_deleted set to false.
+    pub async fn logout(self) -> Result<LogoutResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let not_logged_in = LogoutResponse {
+            result: PCloudResult::Ok,
+            auth_deleted: Some(false),
+        };
+
+        let session = match self.session_token.as_ref() {
+            Some(session) => session,
+            None => return Ok(not_logged_in),
+        };
+
+        if session.logged_out.swap(true, Ordering::SeqCst) {
+            return Ok(not_logged_in);
+        }
+
+        match PCloudClient::revoke_token(&session.client, &session.api_host, &session.token).await {
+            Ok(response) => {
+                debug!("Successful logout");
+                Ok(response)
+            }
+            Err(e) => {
+                // Let drop try again
+                session.logged_out.store(false, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    /// Prepares the given request right before it is sent: sets the User-Agent and logs the request (if enabled). Requests without authentication (like downloads from the content servers) have to pass this method, all others pass it with add_token().
+    pub(crate) fn decorate(&self, r: RequestBuilder) -> RequestBuilder {
+        let r = match &self.user_agent {
+            Some(user_agent) => r.header(reqwest::header::USER_AGENT, user_agent.as_str()),
+            None => r,
+        };
+        self.log_request(&r);
+        r
+    }
+
+    /// Decorates the given request (see decorate()) and authenticates it (see authenticate())
     pub(crate) fn add_token(&self, r: RequestBuilder) -> RequestBuilder {
+        self.authenticate(self.decorate(r))
+    }
+
+    /// If theres is a session token or an OAuth token present, add it to the given request. An auth token set with with_auth_token() takes precedence over both.
+    fn authenticate(&self, r: RequestBuilder) -> RequestBuilder {
+        if let Some(token) = &self.auth_token {
+            return r.query(&[("auth", token.as_str())]);
+        }
+
+        if let Some(header) = &self.oauth_token {
+            return r.header(reqwest::header::AUTHORIZATION, header.as_ref().clone());
+        }
+
         let arc = self.session_token.clone();
 
         if let Some(ref session) = *arc {
@@ -172,12 +596,23 @@ impl PCloudClient {
         return r;
     }
 
-    // Determine fastest api server for the given default api server (either api.pcloud.com or eapi.pcloud.com)
-    async fn get_best_api_server(
+    /// Central execution of API requests: Adds the auth token, sends the request, deserializes the JSON response and checks its result
+    pub(crate) async fn execute_json<T: DeserializeOwned + WithPCloudResult>(
+        &self,
+        r: RequestBuilder,
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let r = self.add_token(r);
+
+        let result = r.send().await?.json::<T>().await?.assert_ok()?;
+        Ok(result)
+    }
+
+    /// Requests the api servers for the given default api server (either api.pcloud.com or eapi.pcloud.com)
+    async fn fetch_api_servers(
         client: &reqwest::Client,
         host: &str,
         session_token: Option<String>,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<ApiServers, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!("{}/getapiserver", host);
 
         let mut r = client.get(url);
@@ -186,7 +621,34 @@ impl PCloudClient {
             r = r.query(&[("auth", v)]);
         }
 
-        let api_servers = r.send().await?.json::<pcloud_model::ApiServers>().await?;
+        let api_servers = r.send().await?.json::<ApiServers>().await?;
+        Ok(api_servers)
+    }
+
+    /// Returns all candidate hosts (for the rest and the binary API) for the default api server given on creation, ordered by preference.
+    pub async fn api_servers(
+        &self,
+    ) -> Result<ApiServers, Box<dyn std::error::Error + Send + Sync>> {
+        let token = self
+            .session_token
+            .as_ref()
+            .as_ref()
+            .map(|s| s.token.clone());
+
+        let api_servers =
+            PCloudClient::fetch_api_servers(&self.client, &self.api_host.default_host, token)
+                .await?
+                .assert_ok()?;
+        Ok(api_servers)
+    }
+
+    // Determine fastest api server for the given default api server (either api.pcloud.com or eapi.pcloud.com)
+    async fn get_best_api_server(
+        client: &reqwest::Client,
+        host: &str,
+        session_token: Option<String>,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let api_servers = PCloudClient::fetch_api_servers(client, host, session_token).await?;
 
         let best_host = match api_servers.result {
             pcloud_model::PCloudResult::Ok => {
@@ -208,13 +670,104 @@ impl PCloudClient {
         &self,
     ) -> Result<UserInfo, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!("{}/userinfo", self.api_host);
-        let mut r = self.client.get(url);
-
-        r = self.add_token(r);
+        let r = self.client.get(url);
 
         debug!("Requesting user info");
-        let user_info = r.send().await?.json::<UserInfo>().await?.assert_ok()?;
+        let user_info = self.execute_json::<UserInfo>(r).await?;
 
         Ok(user_info)
     }
+
+    /// Checks if there is enough free space left to store the given number of bytes. Fails with UserOverQuota if not. Useful to check before large uploads, instead of failing in the middle of the transfer. Logs a warning if the space runs low.
+    pub async fn ensure_space_for(
+        &self,
+        bytes: u64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let user_info = self.get_user_info().await?;
+
+        match user_info.free_bytes() {
+            Some(free) if free < bytes => {
+                warn!(
+                    "Not enough space left: {} bytes requested, but only {} bytes free",
+                    bytes, free
+                );
+                Err(PCloudResult::UserOverQuota)?
+            }
+            Some(free) => {
+                if let Some(quota) = user_info.quota {
+                    // Warn if less than 10% of the quota will be left
+                    if (free - bytes) < quota / 10 {
+                        warn!(
+                            "Running low on space: only {} bytes left after storing {} bytes",
+                            free - bytes,
+                            bytes
+                        );
+                    }
+                }
+                Ok(())
+            }
+            // Quota unknown, let the server decide
+            None => Ok(()),
+        }
+    }
+
+    /// Get the IP address and country of the client as seen by the pCloud server. Useful for diagnostics
+    pub async fn get_ip(&self) -> Result<ClientIp, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/getip", self.api_host);
+        let r = self.client.get(url);
+
+        debug!("Requesting client ip");
+        let result = self.execute_json::<ClientIp>(r).await?;
+
+        Ok(result)
+    }
+
+    /// Get the host name and ip address of the pCloud server currently serving the requests. Useful for diagnostics
+    pub async fn current_server(
+        &self,
+    ) -> Result<CurrentServer, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/currentserver", self.api_host);
+        let r = self.client.get(url);
+
+        debug!("Requesting current server");
+        let result = self.execute_json::<CurrentServer>(r).await?;
+
+        Ok(result)
+    }
+
+    /// Lists the languages supported by pCloud (language code -> name of the language)
+    pub async fn supported_languages(
+        &self,
+    ) -> Result<SupportedLanguages, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/supportedlanguages", self.api_host);
+        let r = self.client.get(url);
+
+        debug!("Requesting supported languages");
+        let result = self.execute_json::<SupportedLanguages>(r).await?;
+
+        Ok(result)
+    }
+
+    /// Sets the language of the user. The language code is validated against the supported languages before it is sent.
+    pub async fn set_language(
+        &self,
+        language: &str,
+    ) -> Result<SetLanguageResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let supported = self.supported_languages().await?;
+
+        if !supported.languages.contains_key(language) {
+            warn!("Language {} is not supported by pCloud", language);
+            Err(PCloudResult::ProvideLanguage)?
+        }
+
+        let url = format!("{}/setlanguage", self.api_host);
+        let mut r = self.client.get(url);
+
+        r = r.query(&[("language", language)]);
+
+        debug!("Setting user language to {}", language);
+        let result = self.execute_json::<SetLanguageResponse>(r).await?;
+
+        Ok(result)
+    }
 }
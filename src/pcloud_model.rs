@@ -1,9 +1,11 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 
+use crate::file_ops::PCloudFile;
+
 /// # ERRORS
 /// There are number of cases when you request can't be processed as is and an error will be returned. Error codes are always 4 digit. They can be grouped into few categories depending on the type of error occurred.
 /// @see https://docs.pcloud.com/errors/
@@ -29,6 +31,7 @@ pub enum PCloudResult {
     NoFileIdOrPathProvided = 1004,
     InvalidFileDescriptor = 1007,
     DateTimeFormatNotUnderstood = 1013,
+    ProvideLanguage = 1014,
     NoFullToPathOrToNameAndToFolderIdProvided = 1016,
     InvalidFolderId = 1017,
     InvalidFileId = 1018,
@@ -38,6 +41,7 @@ pub enum PCloudResult {
     InvalidFileOrFolderName = 2001,
     ComponentOfTheParentDirectoryDoesNotExist = 2002,
     AccessDenied = 2003,
+    FileOrFolderAlreadyExists = 2004,
     DirectoryDoesNotExist = 2005,
     FolderIsNotEmpty = 2006,
     CanNotDeleteRootFolder = 2007,
@@ -73,6 +77,7 @@ impl Display for PCloudResult {
             PCloudResult::DateTimeFormatNotUnderstood => {
                 write!(f, "Date time format not understood")
             }
+            PCloudResult::ProvideLanguage => write!(f, "Please provide a supported 'language'."),
 
             PCloudResult::ProvideURL => write!(f, "Provide url"),
             PCloudResult::LoginFailed => write!(f, "Log in failed"),
@@ -81,6 +86,7 @@ impl Display for PCloudResult {
                 write!(f, "A component of the parent directory does not exist")
             }
             PCloudResult::AccessDenied => write!(f, "Access denied"),
+            PCloudResult::FileOrFolderAlreadyExists => write!(f, "File or folder already exists"),
             PCloudResult::DirectoryDoesNotExist => write!(f, "Directory does not exist"),
             PCloudResult::UserOverQuota => write!(f, "User over quota"),
             PCloudResult::FileNotFound => write!(f, "File not found"),
@@ -126,8 +132,90 @@ impl Display for PCloudResult {
 /// PCloudResult implements the Error trait
 impl std::error::Error for PCloudResult {}
 
+/// Error returned if an operation would overwrite an already existing file. Contains the metadata of the conflicting file.
+#[derive(Debug, Clone)]
+pub struct AlreadyExistsError {
+    /// Metadata of the already existing file
+    pub metadata: Metadata,
+}
+
+impl Display for AlreadyExistsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            PCloudResult::FileOrFolderAlreadyExists,
+            self.metadata.name
+        )
+    }
+}
+
+impl std::error::Error for AlreadyExistsError {}
+
+/// Error returned if the checksum of downloaded content does not match the checksum reported by pCloud
+#[derive(Debug, Clone)]
+pub struct ChecksumMismatchError {
+    /// Checksum reported by pCloud
+    pub expected: String,
+    /// Checksum of the downloaded content
+    pub actual: String,
+}
+
+impl Display for ChecksumMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch: expected {}, but got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatchError {}
+
+/// Error returned if a file is too large to be read into memory
+#[derive(Debug, Clone)]
+pub struct FileTooLargeError {
+    /// Maximum allowed size in bytes
+    pub max_size: u64,
+}
+
+impl Display for FileTooLargeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "File is larger than {} bytes", self.max_size)
+    }
+}
+
+impl std::error::Error for FileTooLargeError {}
+
+/// Error returned if an audio bit rate outside of the range supported by pCloud is requested
+#[derive(Debug, Clone)]
+pub struct InvalidBitrateError {
+    /// Requested bit rate in kilobits
+    pub bitrate: u32,
+}
+
+impl Display for InvalidBitrateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Audio bit rate {} kbit/s is not within 16 to 320 kbit/s",
+            self.bitrate
+        )
+    }
+}
+
+impl std::error::Error for InvalidBitrateError {}
+
+/// Some payloads (like the userinfo of diff events) do not carry a result, so default to Ok
+impl Default for PCloudResult {
+    fn default() -> Self {
+        PCloudResult::Ok
+    }
+}
+
 /// Category of the file
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Clone)]
 #[repr(u8)]
 pub enum FileCategory {
     Uncategorized = 0,
@@ -139,8 +227,7 @@ pub enum FileCategory {
 }
 
 /// Icon of the file / folder
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, PartialEq, Clone)]
 pub enum FileIcon {
     Document,
     Database,
@@ -158,6 +245,86 @@ pub enum FileIcon {
     Video,
     File,
     Folder,
+    /// Any icon unknown to this client (or a numeric icon id, if a different icon format was requested)
+    Other(String),
+}
+
+impl FileIcon {
+    /// Name of the icon as used by pCloud
+    pub fn as_str(&self) -> &str {
+        match self {
+            FileIcon::Document => "document",
+            FileIcon::Database => "database",
+            FileIcon::Archive => "archive",
+            FileIcon::Web => "web",
+            FileIcon::Gis => "gis",
+            FileIcon::Spreadsheet => "spreadsheet",
+            FileIcon::Font => "font",
+            FileIcon::Presentation => "presentation",
+            FileIcon::Image => "image",
+            FileIcon::Diskimage => "diskimage",
+            FileIcon::Package => "package",
+            FileIcon::Executable => "executable",
+            FileIcon::Audio => "audio",
+            FileIcon::Video => "video",
+            FileIcon::File => "file",
+            FileIcon::Folder => "folder",
+            FileIcon::Other(v) => v.as_str(),
+        }
+    }
+}
+
+impl From<&str> for FileIcon {
+    fn from(value: &str) -> Self {
+        match value {
+            "document" => FileIcon::Document,
+            "database" => FileIcon::Database,
+            "archive" => FileIcon::Archive,
+            "web" => FileIcon::Web,
+            "gis" => FileIcon::Gis,
+            "spreadsheet" => FileIcon::Spreadsheet,
+            "font" => FileIcon::Font,
+            "presentation" => FileIcon::Presentation,
+            "image" => FileIcon::Image,
+            "diskimage" => FileIcon::Diskimage,
+            "package" => FileIcon::Package,
+            "executable" => FileIcon::Executable,
+            "audio" => FileIcon::Audio,
+            "video" => FileIcon::Video,
+            "file" => FileIcon::File,
+            "folder" => FileIcon::Folder,
+            other => FileIcon::Other(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for FileIcon {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Unknown icons must not break the deserialization of the whole metadata, so the icon is deserialized by hand
+impl<'de> Deserialize<'de> for FileIcon {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawIcon {
+            Name(String),
+            Id(u64),
+        }
+
+        match RawIcon::deserialize(deserializer)? {
+            RawIcon::Name(name) => Ok(FileIcon::from(name.as_str())),
+            RawIcon::Id(id) => Ok(FileIcon::Other(id.to_string())),
+        }
+    }
 }
 
 /// Implemented by all structs having a PCloud Result
@@ -180,6 +347,8 @@ pub trait WithPCloudResult {
 /// Result of the `getpublinkdownload` or `getfilelink` calls
 /// see https://docs.pcloud.com/methods/public_links/getpublinkdownload.html
 /// see https://docs.pcloud.com/methods/streaming/getfilelink.html
+/// see https://docs.pcloud.com/methods/streaming/getaudiolink.html
+/// see https://docs.pcloud.com/methods/public_links/getpubthumblink.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DownloadLink {
     pub result: PCloudResult,
@@ -190,9 +359,26 @@ pub struct DownloadLink {
     pub expires: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub hosts: Vec<String>,
+    /// File the link was requested for, to be able to request a fresh link once it expired
+    #[serde(skip)]
+    pub(crate) source: Option<PCloudFile>,
 }
 
 impl DownloadLink {
+    /// Checks if the link is expired. Links without expiry date never expire.
+    pub fn is_expired(&self) -> bool {
+        match self.expires {
+            Some(expires) => expires <= Utc::now(),
+            None => false,
+        }
+    }
+
+    /// Remaining time until the link expires (zero if already expired). None if the link has no expiry date.
+    pub fn valid_for(&self) -> Option<chrono::Duration> {
+        self.expires
+            .map(|expires| (expires - Utc::now()).max(chrono::Duration::zero()))
+    }
+
     /// Converts the given DownloadLink into a full download url for the file. There is no authentication necessary to download the file!
     pub fn into_url(&self) -> Option<String> {
         if self.result == PCloudResult::Ok && self.hosts.len() > 0 && self.path.is_some() {
@@ -248,6 +434,188 @@ impl WithPCloudResult for PublicFileLink {
     }
 }
 
+/// Public link of the user with its access statistics
+/// see https://docs.pcloud.com/methods/public_links/listpublinks.html
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublicLinkInfo {
+    /// ID that can be used to delete/modify this public link
+    pub linkid: u64,
+    /// link's code that can be used to retrieve the public link contents
+    pub code: Option<String>,
+    /// Full link
+    pub link: Option<String>,
+    /// date/time when the link was created
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub created: Option<DateTime<Utc>>,
+    /// date/time when the link was modified
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub modified: Option<DateTime<Utc>>,
+    /// date/time when the link stops working
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub expires: Option<DateTime<Utc>>,
+    /// number of downloads so far
+    #[serde(default)]
+    pub downloads: u64,
+    /// traffic in bytes caused by the link so far
+    #[serde(default)]
+    pub traffic: u64,
+    /// maximum number of downloads, if limited
+    pub maxdownloads: Option<u64>,
+    /// maximum traffic in bytes, if limited
+    pub maxtraffic: Option<u64>,
+    /// Metadata of the target file or folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+/// Result of the `listpublinks` call
+/// see https://docs.pcloud.com/methods/public_links/listpublinks.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PublicLinkList {
+    /// Result of the operation, must be Ok for further values to be present
+    pub result: PCloudResult,
+    /// all public links of the user
+    #[serde(default)]
+    pub publinks: Vec<PublicLinkInfo>,
+}
+
+impl WithPCloudResult for PublicLinkList {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `createuploadlink` call
+/// see https://docs.pcloud.com/methods/upload_links/createuploadlink.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadLink {
+    pub result: PCloudResult,
+    /// ID that can be used to delete/modify this upload link
+    pub uploadlinkid: Option<u64>,
+    /// Full link
+    pub link: Option<String>,
+    /// Mail address to upload files to
+    pub mail: Option<String>,
+    /// link's code that can be used to upload files
+    pub code: Option<String>,
+}
+
+impl WithPCloudResult for UploadLink {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Single upload link, as listed by the `listuploadlinks` call
+/// see https://docs.pcloud.com/methods/upload_links/listuploadlinks.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadLinkEntry {
+    /// ID that can be used to delete/modify this upload link
+    pub uploadlinkid: u64,
+    /// Comment the user will see
+    pub comment: Option<String>,
+    /// Full link
+    pub link: Option<String>,
+    /// Mail address to upload files to
+    pub mail: Option<String>,
+    /// link's code that can be used to upload files
+    pub code: Option<String>,
+    /// date/time when the upload link was created
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub created: Option<DateTime<Utc>>,
+    /// date/time when the upload link was modified
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub modified: Option<DateTime<Utc>>,
+    /// date/time when the upload link expires
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub expires: Option<DateTime<Utc>>,
+    /// Bytes uploaded using this link
+    pub space: Option<u64>,
+    /// Maximum number of bytes which can be uploaded using this link
+    pub maxspace: Option<u64>,
+    /// Number of files uploaded using this link
+    pub files: Option<u64>,
+    /// Maximum number of files which can be uploaded using this link
+    pub maxfiles: Option<u64>,
+    /// Metadata of the target folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+/// Result of the `listuploadlinks` call
+/// see https://docs.pcloud.com/methods/upload_links/listuploadlinks.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadLinkList {
+    pub result: PCloudResult,
+    /// All upload links of the user
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub uploadlinks: Vec<UploadLinkEntry>,
+}
+
+impl WithPCloudResult for UploadLinkList {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `showuploadlink` call
+/// see https://docs.pcloud.com/methods/upload_links/showuploadlink.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadLinkInfo {
+    pub result: PCloudResult,
+    /// Comment the user will see
+    pub comment: Option<String>,
+    /// Mail address to upload files to
+    pub mail: Option<String>,
+    /// true if the current user is the owner of the link
+    pub ownerisme: Option<bool>,
+    /// Name of the owner of the link
+    pub username: Option<String>,
+    /// date/time when the upload link expires
+    #[serde(with = "pcloud_option_date_format", default)]
+    pub expires: Option<DateTime<Utc>>,
+    /// Bytes uploaded using this link
+    pub space: Option<u64>,
+    /// Maximum number of bytes which can be uploaded using this link
+    pub maxspace: Option<u64>,
+    /// Number of files uploaded using this link
+    pub files: Option<u64>,
+    /// Maximum number of files which can be uploaded using this link
+    pub maxfiles: Option<u64>,
+}
+
+impl WithPCloudResult for UploadLinkInfo {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `deleteuploadlink` call
+/// see https://docs.pcloud.com/methods/upload_links/deleteuploadlink.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeleteUploadLinkResponse {
+    pub result: PCloudResult,
+}
+
+impl WithPCloudResult for DeleteUploadLinkResponse {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `trash_clear` call
+/// see https://docs.pcloud.com/methods/trash/trash_clear.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrashClearResponse {
+    pub result: PCloudResult,
+}
+
+impl WithPCloudResult for TrashClearResponse {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
 /// Result of the `diff` call
 /// see https://docs.pcloud.com/methods/general/diff.html
 #[derive(Serialize, Deserialize, Debug)]
@@ -291,6 +659,9 @@ pub struct DiffEntry {
     /// Share metdata of the file / folder targeted by the event
     #[serde(skip_serializing_if = "Option::is_none")]
     pub share: Option<Share>,
+    /// Updated user info, provided with the modifyuserinfo event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userinfo: Option<UserInfo>,
 }
 
 /// Event can be one of:
@@ -365,7 +736,7 @@ pub struct Share {
 
 /// The metadata for a file or folder normally consists of:
 /// see https://docs.pcloud.com/structures/metadata.html
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Metadata {
     // is the folderid of the folder the object resides in
     pub parentfolderid: Option<u64>,
@@ -401,7 +772,7 @@ pub struct Metadata {
     pub fileid: Option<u64>,
     ///  It is possible that as a result of renamefile operation a file with the same name gets deleted (e.g. file old.txt is renamed to new.txt when new.txt already exists in this folder). In these cases deletedfileid is set to fileid of the deleted file.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub deletefileid: Option<String>,
+    pub deletedfileid: Option<u64>,
     /// creation date of the object
     #[serde(with = "pcloud_date_format")]
     pub created: DateTime<Utc>,
@@ -480,7 +851,236 @@ pub struct Metadata {
     pub rotate: Option<u16>,
 }
 
+impl Metadata {
+    /// Splits the flat metadata into a type-safe file or folder view. Fails with InvalidFileId / InvalidFolderId if the id of the file or folder (or of any nested entry) is missing.
+    pub fn classify(&self) -> Result<Entry, PCloudResult> {
+        self.clone().try_into()
+    }
+
+    /// Is the file or folder owned by another user and shared with the current user?
+    pub fn is_shared_in(&self) -> bool {
+        !self.ismine
+    }
+
+    /// Is the file or folder owned by the current user and shared with other users?
+    pub fn is_shared_out(&self) -> bool {
+        self.ismine && self.isshared
+    }
+
+    /// Fills the path of this entry with the given base path and the paths of all nested entries (of a recursive listing) by joining the paths of their parents with their names
+    pub fn resolve_paths(&mut self, base: &str) {
+        self.path = Some(base.to_string());
+
+        let parent = base.trim_end_matches('/');
+        for child in self.contents.iter_mut() {
+            let path = format!("{}/{}", parent, child.name);
+            child.resolve_paths(&path);
+        }
+    }
+}
+
+/// Type-safe view on a Metadata object: Either a file or a folder
+#[derive(Debug, Clone)]
+pub enum Entry {
+    File(FileMetadata),
+    Folder(FolderMetadata),
+}
+
+impl Entry {
+    /// Name of the file or folder
+    pub fn name(&self) -> &str {
+        match self {
+            Entry::File(f) => &f.name,
+            Entry::Folder(f) => &f.name,
+        }
+    }
+
+    /// Is it a file?
+    pub fn is_file(&self) -> bool {
+        matches!(self, Entry::File(_))
+    }
+
+    /// Is it a folder?
+    pub fn is_folder(&self) -> bool {
+        matches!(self, Entry::Folder(_))
+    }
+}
+
+impl TryFrom<Metadata> for Entry {
+    type Error = PCloudResult;
+
+    /// Fails if the id is missing, instead of defaulting to 0 (which is the id of the root folder)
+    fn try_from(value: Metadata) -> Result<Self, Self::Error> {
+        if value.isfolder {
+            Ok(Entry::Folder(FolderMetadata {
+                folderid: value.folderid.ok_or(PCloudResult::InvalidFolderId)?,
+                parentfolderid: value.parentfolderid,
+                name: value.name,
+                path: value.path,
+                created: value.created,
+                modified: value.modified,
+                ismine: value.ismine,
+                isshared: value.isshared,
+                isdeleted: value.isdeleted.unwrap_or(false),
+                contents: value
+                    .contents
+                    .into_iter()
+                    .map(Entry::try_from)
+                    .collect::<Result<Vec<Entry>, PCloudResult>>()?,
+            }))
+        } else {
+            Ok(Entry::File(FileMetadata {
+                fileid: value.fileid.ok_or(PCloudResult::InvalidFileId)?,
+                parentfolderid: value.parentfolderid,
+                name: value.name,
+                path: value.path,
+                created: value.created,
+                modified: value.modified,
+                ismine: value.ismine,
+                isshared: value.isshared,
+                isdeleted: value.isdeleted.unwrap_or(false),
+                size: value.size.unwrap_or_default(),
+                hash: value.hash.unwrap_or_default(),
+                contenttype: value.contenttype.unwrap_or_default(),
+                category: value.category.unwrap_or(FileCategory::Uncategorized),
+                icon: value.icon,
+                thumb: value.thumb,
+            }))
+        }
+    }
+}
+
+/// File-only view of a Metadata object
+#[derive(Debug, Clone)]
+pub struct FileMetadata {
+    /// file's fileid
+    pub fileid: u64,
+    /// is the folderid of the folder the object resides in
+    pub parentfolderid: Option<u64>,
+    /// the name of file
+    pub name: String,
+    /// Full path, if provided
+    pub path: Option<String>,
+    /// creation date of the file
+    pub created: DateTime<Utc>,
+    /// modification date of the file
+    pub modified: DateTime<Utc>,
+    /// is the file owned by the user
+    pub ismine: bool,
+    /// is the file shared with other users
+    pub isshared: bool,
+    /// is the file deleted
+    pub isdeleted: bool,
+    /// size in bytes
+    pub size: u64,
+    /// 64 bit integer representing hash of the contents of the file
+    pub hash: u64,
+    /// content-type of the file
+    pub contenttype: String,
+    /// category of the file
+    pub category: FileCategory,
+    /// name of the icon to display
+    pub icon: Option<FileIcon>,
+    /// true if thumbs can be created from the file
+    pub thumb: bool,
+}
+
+/// Folder-only view of a Metadata object
+#[derive(Debug, Clone)]
+pub struct FolderMetadata {
+    /// folderid of the folder
+    pub folderid: u64,
+    /// is the folderid of the folder the object resides in
+    pub parentfolderid: Option<u64>,
+    /// the name of the folder
+    pub name: String,
+    /// Full path, if provided
+    pub path: Option<String>,
+    /// creation date of the folder
+    pub created: DateTime<Utc>,
+    /// modification date of the folder
+    pub modified: DateTime<Utc>,
+    /// is the folder owned by the user
+    pub ismine: bool,
+    /// is the folder shared with other users
+    pub isshared: bool,
+    /// is the folder deleted
+    pub isdeleted: bool,
+    /// contents of the folder
+    pub contents: Vec<Entry>,
+}
+
+/// Aggregated size of a folder tree
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct FolderSize {
+    /// Number of files within the folder tree
+    pub files: u64,
+    /// Number of sub folders within the folder tree
+    pub folders: u64,
+    /// Total size of all files in bytes
+    pub total_bytes: u64,
+}
+
+impl FolderSize {
+    /// Computes the size of the given (recursively listed) folder. The folder itself is not counted. Deleted entries are only counted if include_deleted is set.
+    pub fn of(folder: &Metadata, include_deleted: bool) -> FolderSize {
+        let mut result = FolderSize::default();
+        // Walk the tree iteratively to avoid deep recursion on deeply nested folders
+        let mut pending: Vec<&Metadata> = folder.contents.iter().collect();
+
+        while let Some(entry) = pending.pop() {
+            if result.add(entry, include_deleted) && entry.isfolder {
+                pending.extend(entry.contents.iter());
+            }
+        }
+
+        result
+    }
+
+    /// Counts a single file or folder (without its contents), e.g. while walking or streaming a listing. Deleted entries are only counted if include_deleted is set. Returns whether the entry was counted.
+    pub fn add(&mut self, entry: &Metadata, include_deleted: bool) -> bool {
+        if !include_deleted && entry.isdeleted.unwrap_or(false) {
+            return false;
+        }
+
+        if entry.isfolder {
+            self.folders += 1;
+        } else {
+            self.files += 1;
+            self.total_bytes += entry.size.unwrap_or(0);
+        }
+        true
+    }
+}
+
+/// Lean representation of a folder hierarchy, without any files
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FolderNode {
+    /// id of the folder
+    pub id: u64,
+    /// name of the folder
+    pub name: String,
+    /// sub folders of the folder
+    pub children: Vec<FolderNode>,
+}
+
+impl FolderNode {
+    /// Builds the folder tree from the given (recursively listed) folder. Files are ignored. Returns None if the metadata does not describe a folder.
+    pub fn of(folder: &Metadata) -> Option<FolderNode> {
+        if !folder.isfolder {
+            return None;
+        }
+
+        Some(FolderNode {
+            id: folder.folderid?,
+            name: folder.name.clone(),
+            children: folder.contents.iter().filter_map(FolderNode::of).collect(),
+        })
+    }
+}
+
 /// Result of the `getapiserver`request
+/// see https://docs.pcloud.com/methods/general/getapiserver.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiServers {
     /// Result of the operation, must be Ok for further values to be present
@@ -499,9 +1099,50 @@ impl WithPCloudResult for ApiServers {
     }
 }
 
+/// Result of the `getip` request
+/// see https://docs.pcloud.com/methods/general/getip.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClientIp {
+    /// Result of the operation, must be Ok for further values to be present
+    pub result: PCloudResult,
+    /// IP address of the client as seen by the server
+    pub ip: Option<String>,
+    /// lowercase two-letter country code of the client
+    pub country: Option<String>,
+}
+
+impl WithPCloudResult for ClientIp {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `currentserver` request
+/// see https://docs.pcloud.com/methods/general/currentserver.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrentServer {
+    /// Result of the operation, must be Ok for further values to be present
+    pub result: PCloudResult,
+    /// IP address of the server
+    pub ip: Option<String>,
+    /// IP address of the server for the binary protocol
+    pub ipbin: Option<String>,
+    /// IPv6 address of the server
+    pub ipv6: Option<String>,
+    /// Host name of the server
+    pub hostname: Option<String>,
+}
+
+impl WithPCloudResult for CurrentServer {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
 /// Result of fetching metadata of files or folders
 /// see https://docs.pcloud.com/methods/file/stat.html
 /// see https://docs.pcloud.com/methods/folder/listfolder.html
+/// see https://docs.pcloud.com/methods/public_links/copypubfile.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FileOrFolderStat {
     /// Result of the operation, must be Ok for further values to be present
@@ -517,7 +1158,55 @@ impl WithPCloudResult for FileOrFolderStat {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl FileOrFolderStat {
+    /// Synthesized successful result for operations skipped in dry-run mode. No metadata is available.
+    pub(crate) fn dry_run() -> FileOrFolderStat {
+        FileOrFolderStat {
+            result: PCloudResult::Ok,
+            metadata: None,
+        }
+    }
+
+    /// Metadata of the targeted file or folder. Fails with InternalError, if the server returned no metadata despite a successful result (or with the result of the operation, if it failed).
+    pub fn metadata_or_err(&self) -> Result<&Metadata, PCloudResult> {
+        if self.result != PCloudResult::Ok {
+            return Err(self.result.clone());
+        }
+        self.metadata.as_ref().ok_or(PCloudResult::InternalError)
+    }
+
+    /// File id of the targeted file, if present
+    pub fn file_id(&self) -> Option<u64> {
+        self.metadata.as_ref().and_then(|m| m.fileid)
+    }
+
+    /// Folder id of the targeted folder, if present
+    pub fn folder_id(&self) -> Option<u64> {
+        self.metadata.as_ref().and_then(|m| m.folderid)
+    }
+
+    /// Name of the targeted file or folder, if present
+    pub fn name(&self) -> Option<&str> {
+        self.metadata.as_ref().map(|m| m.name.as_str())
+    }
+
+    /// Id of the file, which was overwritten (and therefore deleted) by a move or rename operation, if any
+    pub fn overwrote_file_id(&self) -> Option<u64> {
+        self.metadata.as_ref().and_then(|m| m.deletedfileid)
+    }
+
+    /// Number of direct entries of the listed folder (sub folders of recursive listings are not counted into). 0 for files and failed requests.
+    pub fn entry_count(&self) -> usize {
+        self.metadata
+            .as_ref()
+            .map(|m| m.contents.len())
+            .unwrap_or(0)
+    }
+}
+
+/// A single revision of a file
+/// see https://docs.pcloud.com/structures/revision.html
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileRevision {
     /// id of the revision
     pub revisionid: u64,
@@ -550,6 +1239,20 @@ impl WithPCloudResult for RevisionList {
     }
 }
 
+impl RevisionList {
+    /// Returns the revision with the given id, if present
+    pub fn find(&self, revision_id: u64) -> Option<&FileRevision> {
+        self.revisions.iter().find(|r| r.revisionid == revision_id)
+    }
+
+    /// Returns the revisions ordered from the newest to the oldest
+    pub fn newest_first(&self) -> Vec<&FileRevision> {
+        let mut result: Vec<&FileRevision> = self.revisions.iter().collect();
+        result.sort_by(|a, b| b.created.cmp(&a.created));
+        result
+    }
+}
+
 /// Result of the deletefolderrecursive operation
 /// see https://docs.pcloud.com/methods/folder/deletefolderrecursive.html
 #[derive(Serialize, Deserialize, Debug)]
@@ -570,11 +1273,32 @@ impl WithPCloudResult for FolderRecursivlyDeleted {
     }
 }
 
+impl FolderRecursivlyDeleted {
+    /// Synthesized successful result for operations skipped in dry-run mode. The number of deleted files and folders is unknown.
+    pub(crate) fn dry_run() -> FolderRecursivlyDeleted {
+        FolderRecursivlyDeleted {
+            result: PCloudResult::Ok,
+            deletedfiles: None,
+            deletedfolders: None,
+        }
+    }
+}
+
+/// Result of deleting a file, including whether it went to the trash
+#[derive(Debug, Clone)]
+pub struct DeleteResult {
+    /// Metadata of the deleted file (not present in dry-run mode)
+    pub metadata: Option<Metadata>,
+    /// Was the file moved to the trash (and can still be restored)? False if it was purged immediately.
+    pub trashed: bool,
+}
+
 /// Result of calculating file checksums
 /// see https://docs.pcloud.com/methods/file/checksumfile.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FileChecksums {
-    /// Result of the operation, must be Ok for further values to be present
+    /// Result of the operation, must be Ok for further values to be present. Missing for the checksums returned by uploadfile.
+    #[serde(default)]
     pub result: PCloudResult,
     /// Metdata of the target file
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -596,11 +1320,38 @@ impl WithPCloudResult for FileChecksums {
     }
 }
 
+/// Kinds of digests pCloud calculates for files, ordered from the strongest to the weakest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestKind {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl FileChecksums {
+    /// Returns the digest of the given kind, if pCloud returned it (depends on the region of the API server)
+    pub fn get(&self, kind: DigestKind) -> Option<&str> {
+        match kind {
+            DigestKind::Sha256 => self.sha256.as_deref(),
+            DigestKind::Sha1 => self.sha1.as_deref(),
+            DigestKind::Md5 => self.md5.as_deref(),
+        }
+    }
+
+    /// Returns the strongest digest available, so verification code does not have to care about the region of the API server. Only None if the request failed.
+    pub fn best(&self) -> Option<(DigestKind, &str)> {
+        [DigestKind::Sha256, DigestKind::Sha1, DigestKind::Md5]
+            .into_iter()
+            .find_map(|kind| self.get(kind).map(|digest| (kind, digest)))
+    }
+}
+
 /// Result of fetching user metadata
 /// see https://docs.pcloud.com/methods/general/userinfo.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserInfo {
-    /// Result of the operation, must be Ok for further values to be present
+    /// Result of the operation, must be Ok for further values to be present. Not present (and therefore Ok) for the userinfo of diff events.
+    #[serde(default)]
     pub result: PCloudResult,
     /// Authentication token (only present if getauth query parameter was set)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -624,22 +1375,96 @@ pub struct UserInfo {
     pub quota: Option<u64>,
 }
 
+impl UserInfo {
+    /// Free space in bytes (zero if over quota). None if the quota is unknown.
+    pub fn free_bytes(&self) -> Option<u64> {
+        match (self.quota, self.usedquota) {
+            (Some(quota), Some(used)) => Some(quota.saturating_sub(used)),
+            _ => None,
+        }
+    }
+
+    /// Fraction of the quota used (1.0 means full). None if the quota is unknown.
+    pub fn usage_fraction(&self) -> Option<f64> {
+        match (self.quota, self.usedquota) {
+            (Some(quota), Some(used)) if quota > 0 => Some(used as f64 / quota as f64),
+            _ => None,
+        }
+    }
+
+    /// Checks if more space is used than available
+    pub fn is_over_quota(&self) -> bool {
+        match (self.quota, self.usedquota) {
+            (Some(quota), Some(used)) => used > quota,
+            _ => false,
+        }
+    }
+}
+
 impl WithPCloudResult for UserInfo {
     fn get_result(&self) -> &PCloudResult {
         &self.result
     }
 }
 
+/// Result of the `supportedlanguages` call
+/// see https://docs.pcloud.com/methods/general/supportedlanguages.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SupportedLanguages {
+    /// Result of the operation, must be Ok for further values to be present
+    pub result: PCloudResult,
+    /// Supported languages: language code -> name of the language
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub languages: HashMap<String, String>,
+}
+
+impl WithPCloudResult for SupportedLanguages {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
+/// Result of the `setlanguage` call
+/// see https://docs.pcloud.com/methods/general/setlanguage.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetLanguageResponse {
+    /// Result of the operation
+    pub result: PCloudResult,
+}
+
+impl WithPCloudResult for SetLanguageResponse {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
 /// Result of a file upload operation
 /// see https://docs.pcloud.com/methods/file/uploadfile.html
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UploadedFile {
     /// Result of the operation, must be Ok for further values to be present
     pub result: PCloudResult,
+    /// ids of the uploaded files, in the order the files were added to the upload (same order as metadata)
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub fileids: Vec<u64>,
+    /// metadata of the uploaded files, in the order the files were added to the upload (same order as fileids)
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub metadata: Vec<Metadata>,
+    /// checksums of the uploaded files (same order as fileids). Only guaranteed to be complete, if requested with UploadRequestBuilder::request_checksums().
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub checksums: Vec<FileChecksums>,
+}
+
+impl UploadedFile {
+    /// Total size of all uploaded files in bytes
+    pub fn total_size(&self) -> u64 {
+        self.metadata.iter().filter_map(|m| m.size).sum()
+    }
+
+    /// Names of all uploaded files
+    pub fn names(&self) -> Vec<&str> {
+        self.metadata.iter().map(|m| m.name.as_str()).collect()
+    }
 }
 
 impl WithPCloudResult for UploadedFile {
@@ -648,6 +1473,38 @@ impl WithPCloudResult for UploadedFile {
     }
 }
 
+/// Progress of a running upload, as received by the server
+/// see https://docs.pcloud.com/methods/file/uploadprogress.html
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadProgressResponse {
+    /// Result of the operation, must be Ok for further values to be present
+    pub result: PCloudResult,
+    /// total size of the upload in bytes (as announced by the client, might be 0 for streamed bodies of unknown length)
+    #[serde(default)]
+    pub total: u64,
+    /// bytes received by the server so far
+    #[serde(default)]
+    pub uploaded: u64,
+    /// name of the file currently uploaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currentfile: Option<String>,
+    /// bytes of the current file received so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currentfileuploaded: Option<u64>,
+    /// metadata of the files already uploaded completely
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub files: Vec<Metadata>,
+    /// is the upload completed?
+    #[serde(default)]
+    pub complete: bool,
+}
+
+impl WithPCloudResult for UploadProgressResponse {
+    fn get_result(&self) -> &PCloudResult {
+        &self.result
+    }
+}
+
 /// Result of log out
 /// see https://docs.pcloud.com/methods/auth/logout.html
 #[derive(Serialize, Deserialize, Debug)]
@@ -726,6 +1583,21 @@ pub struct SaveZipProgressResponse {
     pub totalbytes: u64,
 }
 
+/// Joins a parent folder path and the name of a file or folder into a normalized, absolute pCloud path. Surplus slashes are removed, a leading slash is guaranteed. Names containing a slash are rejected.
+pub fn join_path(parent: &str, name: &str) -> Result<String, PCloudResult> {
+    if name.is_empty() || name.contains('/') {
+        return Err(PCloudResult::InvalidFileOrFolderName);
+    }
+
+    let components: Vec<&str> = parent.split('/').filter(|c| !c.is_empty()).collect();
+
+    if components.is_empty() {
+        Ok(format!("/{}", name))
+    } else {
+        Ok(format!("/{}/{}", components.join("/"), name))
+    }
+}
+
 /// Converts a DateTime for pCloud URLs
 pub fn format_date_time_for_pcloud<Tz>(datetime: &DateTime<Tz>) -> String
 where
@@ -736,10 +1608,44 @@ where
     format!("{}", datetime.format(format))
 }
 
+/// Parses a date in the pCloud date format, preserving the offset given by the server. The models of this crate convert all dates to UTC, which loses no information as long as pCloud sends its dates with +0000.
+pub fn parse_date_time_from_pcloud(
+    value: &str,
+) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
+    let format = "%a, %d %b %Y %H:%M:%S %z";
+    DateTime::parse_from_str(value, format)
+}
+
+/// Date as returned by pCloud: Either in the pCloud date format or as unix epoch seconds (returned by some endpoints)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PCloudDateValue {
+    Formatted(String),
+    Epoch(i64),
+}
+
+impl PCloudDateValue {
+    /// Parses the date, preserving the offset given by the server (epoch seconds are UTC)
+    fn parse(self) -> Result<DateTime<FixedOffset>, String> {
+        match self {
+            PCloudDateValue::Formatted(s) => {
+                parse_date_time_from_pcloud(&s).map_err(|e| e.to_string())
+            }
+            PCloudDateValue::Epoch(secs) => Utc
+                .timestamp_opt(secs, 0)
+                .single()
+                .map(|date| date.with_timezone(&FixedOffset::east_opt(0).unwrap()))
+                .ok_or_else(|| format!("Timestamp {} is out of range", secs)),
+        }
+    }
+}
+
 /// pCloud Date format for serializing / deserializing
 mod pcloud_date_format {
-    use chrono::{DateTime, TimeZone, Utc};
+    use chrono::{DateTime, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
+
+    use super::PCloudDateValue;
     const FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S %z";
 
     // The signature of a serialize_with function must follow the pattern:
@@ -768,16 +1674,19 @@ mod pcloud_date_format {
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        Utc.datetime_from_str(&s, FORMAT)
+        PCloudDateValue::deserialize(deserializer)?
+            .parse()
+            .map(|date| date.with_timezone(&Utc))
             .map_err(serde::de::Error::custom)
     }
 }
 
 /// pCloud Date format for serializing / deserializing optional values
 mod pcloud_option_date_format {
-    use chrono::{DateTime, TimeZone, Utc};
+    use chrono::{DateTime, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
+
+    use super::PCloudDateValue;
     const FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S %z";
 
     // The signature of a serialize_with function must follow the pattern:
@@ -811,12 +1720,13 @@ mod pcloud_option_date_format {
     where
         D: Deserializer<'de>,
     {
-        let inp = String::deserialize(deserializer);
+        let inp = PCloudDateValue::deserialize(deserializer);
 
         match inp {
-            Ok(s) => {
-                let conv = Utc
-                    .datetime_from_str(&s, FORMAT)
+            Ok(v) => {
+                let conv = v
+                    .parse()
+                    .map(|date| date.with_timezone(&Utc))
                     .map_err(serde::de::Error::custom);
 
                 match conv {
@@ -828,3 +1738,64 @@ mod pcloud_option_date_format {
         }
     }
 }
+
+/// pCloud Date format for serializing / deserializing while preserving the offset given by the server. The models of this crate use UTC dates, use it with `#[serde(with = "pcloud_async_api::pcloud_model::pcloud_local_date_format")]` for own types.
+pub mod pcloud_local_date_format {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    use super::PCloudDateValue;
+    const FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S %z";
+
+    pub fn serialize<S>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let s = format!("{}", date.format(FORMAT));
+        serializer.serialize_str(&s)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        PCloudDateValue::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// pCloud Date format for serializing / deserializing optional values while preserving the offset given by the server. The models of this crate use UTC dates, use it with `#[serde(with = "pcloud_async_api::pcloud_model::pcloud_local_option_date_format", default)]` for own types.
+pub mod pcloud_local_option_date_format {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    use super::PCloudDateValue;
+    const FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S %z";
+
+    pub fn serialize<S>(
+        inp: &Option<DateTime<FixedOffset>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match inp {
+            Some(date) => {
+                let s = format!("{}", date.format(FORMAT));
+                serializer.serialize_str(&s)
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match PCloudDateValue::deserialize(deserializer) {
+            Ok(v) => v.parse().map(Some).map_err(serde::de::Error::custom),
+            Err(_) => Ok(None),
+        }
+    }
+}
@@ -1,18 +1,22 @@
 use std::time::Duration;
 
-use log::warn;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use log::{debug, warn};
 use reqwest::Response;
 use tokio::{
+    io::AsyncWrite,
     sync::mpsc::{self, Receiver, Sender},
     time::sleep,
 };
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::{
     file_ops::Tree,
     folder_ops::FolderDescriptor,
     pcloud_client::PCloudClient,
-    pcloud_model::{FileOrFolderStat, SaveZipProgressResponse, WithPCloudResult},
+    pcloud_model::{FileOrFolderStat, SaveZipProgressResponse},
 };
 
 pub struct GetZipRequestBuilder {
@@ -38,13 +42,49 @@ impl GetZipRequestBuilder {
             .client
             .get(format!("{}/getzip", self.client.api_host));
 
-        r = self.tree.add_to_request(r);
+        let tree = self.tree.resolve().await?;
+        r = tree.add_to_request(r);
 
         r = self.client.add_token(r);
 
         let resp = r.send().await?;
         Ok(resp)
     }
+
+    /// Starts creating a zip file from the given files and streams it directly into the writer. Returns the number of bytes written. Fails with `ConnectionBroken` if the connection is lost mid-stream, so a truncated archive is never silently accepted.
+    pub async fn download_to<W: AsyncWrite + Unpin>(
+        self,
+        writer: &mut W,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.download().await?;
+        PCloudClient::write_response_to(response, writer).await
+    }
+
+    /// Starts creating a zip file from the given files and returns the stream of its bytes together with a channel receiving the cumulative number of bytes downloaded so far. Since the zip file is created on-the-fly its total size is unknown. If the receiver does not keep up, intermediate updates are skipped.
+    pub async fn download_with_progress(
+        self,
+    ) -> Result<
+        (
+            impl Stream<Item = Result<Bytes, reqwest::Error>>,
+            Receiver<u64>,
+        ),
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        let response = self.download().await?;
+
+        let (tx, rx) = mpsc::channel::<u64>(32);
+
+        let mut downloaded: u64 = 0;
+        let stream = response.bytes_stream().inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                downloaded += bytes.len() as u64;
+                // Never block the download, the next update contains the cumulative count anyway
+                let _ = tx.try_send(downloaded);
+            }
+        });
+
+        Ok((stream, rx))
+    }
 }
 
 pub struct SaveZipRequestBuilder {
@@ -60,6 +100,8 @@ pub struct SaveZipRequestBuilder {
     to_name: Option<String>,
     /// key to retrieve the progress for the zipping process
     progress_hash: Option<String>,
+    /// If set, the progress notification stops once the token is cancelled
+    cancellation: Option<CancellationToken>,
 }
 
 pub struct InitiateSavezipRequestBuilder {
@@ -88,15 +130,16 @@ impl InitiateSavezipRequestBuilder {
             to_folder_id: None,
             to_name: None,
             progress_hash: None,
+            cancellation: None,
         }
     }
 
     /// Target folder and file name of the target zip file
-    pub fn to_folder<'a, T: FolderDescriptor>(
+    pub fn to_folder<T: FolderDescriptor>(
         self,
         folder_like: T,
         file_name: &str,
-    ) -> Result<SaveZipRequestBuilder, Box<dyn 'a + std::error::Error + Send + Sync>> {
+    ) -> Result<SaveZipRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
         let f = folder_like.to_folder()?;
 
         Ok(SaveZipRequestBuilder {
@@ -106,6 +149,7 @@ impl InitiateSavezipRequestBuilder {
             to_folder_id: f.folder_id,
             to_name: Some(file_name.to_string()),
             progress_hash: None,
+            cancellation: None,
         })
     }
 }
@@ -141,6 +185,12 @@ impl SaveZipRequestBuilder {
         Ok(remaining)
     }
 
+    /// Stops the progress notification of execute_with_progress_notification() as soon as the given token is cancelled. Without a token the notification only stops once the zip file is complete or the receiver is closed.
+    pub fn cancel_on(mut self, token: CancellationToken) -> SaveZipRequestBuilder {
+        self.cancellation = Some(token);
+        self
+    }
+
     ///  Starts creating a zip file in the user's filesystem and notifies the user of the progress
     pub async fn execute_with_progress_notification(
         self,
@@ -151,6 +201,8 @@ impl SaveZipRequestBuilder {
     > {
         let progress_hash = Uuid::new_v4().to_string();
         let progress_client = self.client.clone();
+        // A token which is never cancelled, if none was given
+        let cancellation = self.cancellation.clone().unwrap_or_default();
 
         let req = SaveZipRequestBuilder {
             client: self.client,
@@ -159,13 +211,14 @@ impl SaveZipRequestBuilder {
             to_folder_id: self.to_folder_id,
             to_name: self.to_name,
             progress_hash: Some(progress_hash.clone()),
+            cancellation: None,
         };
         let result = req.execute().await?;
 
         let (tx, rx) = mpsc::channel::<SaveZipProgressResponse>(32);
 
         tokio::spawn(async move {
-            loop {
+            while !tx.is_closed() {
                 match SaveZipRequestBuilder::fetch_progress_and_send_event(
                     &progress_client,
                     &progress_hash,
@@ -182,7 +235,13 @@ impl SaveZipRequestBuilder {
                         warn!("Errors during receiving savezipprogress: {}", err);
                     }
                 };
-                sleep(polling_interval).await;
+                tokio::select! {
+                    _ = cancellation.cancelled() => {
+                        debug!("Savezip progress notification cancelled");
+                        break;
+                    }
+                    _ = sleep(polling_interval) => {}
+                }
             }
         });
 
@@ -196,7 +255,7 @@ impl SaveZipRequestBuilder {
         let mut r = self
             .client
             .client
-            .get(format!("{}/savezip", self.client.api_host));
+            .post(format!("{}/savezip", self.client.api_host));
 
         if let Some(v) = self.to_path {
             r = r.query(&[("topath", v)]);
@@ -214,23 +273,17 @@ impl SaveZipRequestBuilder {
             r = r.query(&[("progresshash", v)]);
         }
 
-        r = self.tree.add_to_request(r);
-
-        r = self.client.add_token(r);
+        let tree = self.tree.resolve().await?;
+        r = tree.add_to_request(r);
 
-        let result = r
-            .send()
-            .await?
-            .json::<FileOrFolderStat>()
-            .await?
-            .assert_ok()?;
+        let result = self.client.execute_json::<FileOrFolderStat>(r).await?;
         Ok(result)
     }
 }
 
 impl PCloudClient {
     /// Creates a zip file on the remote file system with the content specified by the given Tree
-    /// > WARNING: Currently all accesses return `2003 Access denied`.
+    /// > WARNING: Sent as GET, savezip returned `2003 Access denied` for all accesses. It is now sent as POST (like the other write operations, e.g. copyfile), verify it with the integration test of the `remote_zip` feature before relying on it.
     pub fn create_zip(&self, tree: Tree) -> InitiateSavezipRequestBuilder {
         InitiateSavezipRequestBuilder::zip(self, tree)
     }
@@ -238,10 +291,38 @@ impl PCloudClient {
     /// Downloads a zip file of the files requested in the given tree.
     ///  When successful it returns a zip archive over the current API connection with all the files and directories in the requested tree.
     /// If the size of the resulting file is going to be over 4Gb or if it contains more than 65535 entries, the zip64 format is used, otherwise the file is plain zip. This is the fastest way to generate a zip file as the API server will construct the archive on-the-fly for you. Therefore the download will start instantly even with multi-gigabyte files.
+    /// pCloud does not support password protected (encrypted) archives, neither for getzip nor for savezip.
     pub async fn download_zip_of_files(
         &self,
         tree: Tree,
     ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
         GetZipRequestBuilder::zip(self, tree).download().await
     }
+
+    /// Downloads a zip file of the files requested in the given tree as stream of bytes and reports the number of bytes downloaded so far to the returned channel, e.g. to show the throughput of large downloads.
+    pub async fn download_zip_with_progress(
+        &self,
+        tree: Tree,
+    ) -> Result<
+        (
+            impl Stream<Item = Result<Bytes, reqwest::Error>>,
+            Receiver<u64>,
+        ),
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        GetZipRequestBuilder::zip(self, tree)
+            .download_with_progress()
+            .await
+    }
+
+    /// Downloads a zip file of the files requested in the given tree and streams it directly into the writer. Returns the number of bytes written.
+    pub async fn download_zip_of_files_to<W: AsyncWrite + Unpin>(
+        &self,
+        tree: Tree,
+        writer: &mut W,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        GetZipRequestBuilder::zip(self, tree)
+            .download_to(writer)
+            .await
+    }
 }
@@ -0,0 +1,168 @@
+#![cfg(feature = "streaming_listing")]
+
+use std::fmt;
+
+use futures::TryStreamExt;
+use reqwest::Response;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+use crate::pcloud_model::{Metadata, PCloudResult};
+
+/// Single entry of a streamed listing
+pub type ListingEntry = Result<Metadata, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Parses the body of a listfolder response incrementally on a blocking thread and sends all entries of the listed folder (of all levels, without the folder itself) to the returned channel. Only the entries currently parsed (one per nesting level) are kept in memory, the contents of the sent folders are always empty.
+/// Entries are sent in the order they finish parsing, so the entries of a folder are usually sent before the folder itself. Errors (like a failed request or a broken connection) are sent as last entry.
+/// Must be called within a tokio runtime.
+pub(crate) fn stream_listing(response: Response) -> Receiver<ListingEntry> {
+    let (tx, rx) = mpsc::channel::<ListingEntry>(32);
+
+    let bytes = response
+        .bytes_stream()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+    let reader = SyncIoBridge::new(StreamReader::new(Box::pin(bytes)));
+
+    tokio::task::spawn_blocking(move || {
+        let mut deserializer =
+            serde_json::Deserializer::from_reader(std::io::BufReader::new(reader));
+
+        let seed = ResponseSeed { tx: &tx };
+        let error: Option<Box<dyn std::error::Error + Send + Sync>> =
+            match seed.deserialize(&mut deserializer) {
+                Ok(PCloudResult::Ok) => None,
+                Ok(result) => Some(Box::new(result)),
+                Err(e) => Some(Box::new(e)),
+            };
+
+        if let Some(e) = error {
+            // Fails only if the receiver is already gone, then nobody is interested in the error anyway
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+
+    rx
+}
+
+/// Reads the top-level object of the response: the result code and the metadata of the listed folder
+struct ResponseSeed<'a> {
+    tx: &'a Sender<ListingEntry>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ResponseSeed<'a> {
+    type Value = PCloudResult;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<PCloudResult, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ResponseSeed<'a> {
+    type Value = PCloudResult;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a listfolder response")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PCloudResult, A::Error> {
+        let mut result = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "result" => result = Some(map.next_value::<PCloudResult>()?),
+                "metadata" => map.next_value_seed(EntrySeed {
+                    tx: self.tx,
+                    send: false,
+                })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        result.ok_or_else(|| de::Error::missing_field("result"))
+    }
+}
+
+/// Reads a single metadata object. All fields except the contents are collected, the contents are streamed entry by entry.
+struct EntrySeed<'a> {
+    tx: &'a Sender<ListingEntry>,
+    /// Send the entry itself once it is complete (false for the listed folder)
+    send: bool,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for EntrySeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for EntrySeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a metadata object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut fields = Map::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "contents" {
+                map.next_value_seed(ContentsSeed { tx: self.tx })?;
+            } else {
+                fields.insert(key, map.next_value::<Value>()?);
+            }
+        }
+
+        if self.send {
+            let entry: Metadata =
+                serde_json::from_value(Value::Object(fields)).map_err(de::Error::custom)?;
+            // Abort parsing, if the receiver was dropped
+            self.tx
+                .blocking_send(Ok(entry))
+                .map_err(|_| de::Error::custom("receiver of the listing was closed"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the contents array of a folder entry by entry
+struct ContentsSeed<'a> {
+    tx: &'a Sender<ListingEntry>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ContentsSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ContentsSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of metadata objects")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq
+            .next_element_seed(EntrySeed {
+                tx: self.tx,
+                send: true,
+            })?
+            .is_some()
+        {}
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, TimeZone};
+use log::debug;
+
+use crate::{
+    folder_ops::FolderDescriptor,
+    pcloud_client::PCloudClient,
+    pcloud_model::{self, DeleteUploadLinkResponse, UploadLink, UploadLinkInfo, UploadLinkList},
+};
+
+pub struct CreateUploadLinkRequestBuilder {
+    /// Client to actually perform the request
+    client: PCloudClient,
+    /// Path of the target folder
+    path: Option<String>,
+    ///  id of the target folder
+    folder_id: Option<u64>,
+    /// Comment the user will see
+    comment: String,
+    /// Datetime when the link will stop working
+    expire: Option<String>,
+    /// Maximum number of bytes which can be uploaded using this link
+    max_space: Option<u64>,
+    /// Maximum number of files which can be uploaded using this link
+    max_files: Option<u64>,
+}
+
+#[allow(dead_code)]
+impl CreateUploadLinkRequestBuilder {
+    pub(crate) fn for_folder<T: FolderDescriptor>(
+        client: &PCloudClient,
+        folder_like: T,
+        comment: &str,
+    ) -> Result<CreateUploadLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        let f = folder_like.to_folder()?;
+
+        if !f.is_empty() {
+            Ok(CreateUploadLinkRequestBuilder {
+                folder_id: f.folder_id,
+                path: f.path,
+                client: client.clone(),
+                comment: comment.to_string(),
+                expire: None,
+                max_space: None,
+                max_files: None,
+            })
+        } else {
+            Err(pcloud_model::PCloudResult::NoFullPathOrFolderIdProvided)?
+        }
+    }
+
+    /// Datetime when the link will stop working
+    pub fn expire_link_after<Tz>(mut self, value: &DateTime<Tz>) -> CreateUploadLinkRequestBuilder
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        self.expire = Some(pcloud_model::format_date_time_for_pcloud(value));
+        self
+    }
+
+    /// Maximum number of bytes which can be uploaded using this link
+    pub fn with_max_space(mut self, value: u64) -> CreateUploadLinkRequestBuilder {
+        self.max_space = Some(value);
+        self
+    }
+
+    /// Maximum number of files which can be uploaded using this link
+    pub fn with_max_files(mut self, value: u64) -> CreateUploadLinkRequestBuilder {
+        self.max_files = Some(value);
+        self
+    }
+
+    /// Creates the upload link
+    pub async fn execute(self) -> Result<UploadLink, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .client
+            .get(format!("{}/createuploadlink", self.client.api_host));
+
+        if let Some(v) = self.path {
+            debug!("Creating upload link for folder {}", v);
+            r = r.query(&[("path", v)]);
+        }
+
+        if let Some(v) = self.folder_id {
+            debug!("Creating upload link for folder {}", v);
+            r = r.query(&[("folderid", v)]);
+        }
+
+        r = r.query(&[("comment", self.comment)]);
+
+        if let Some(v) = self.expire {
+            r = r.query(&[("expire", v)]);
+        }
+
+        if let Some(v) = self.max_space {
+            r = r.query(&[("maxspace", v)]);
+        }
+
+        if let Some(v) = self.max_files {
+            r = r.query(&[("maxfiles", v)]);
+        }
+
+        let result = self.client.execute_json::<UploadLink>(r).await?;
+        Ok(result)
+    }
+}
+
+impl PCloudClient {
+    /// Creates an upload link, so others can upload files into the given folder. Accepts either a folder id (u64), a folder path (String) or any other pCloud object describing a folder (like Metadata)
+    pub fn create_upload_link<T: FolderDescriptor>(
+        &self,
+        folder_like: T,
+        comment: &str,
+    ) -> Result<CreateUploadLinkRequestBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        CreateUploadLinkRequestBuilder::for_folder(self, folder_like, comment)
+    }
+
+    /// Lists all upload links of the user
+    pub async fn list_upload_links(
+        &self,
+    ) -> Result<UploadLinkList, Box<dyn std::error::Error + Send + Sync>> {
+        let r = self
+            .client
+            .get(format!("{}/listuploadlinks", self.api_host));
+
+        debug!("Requesting upload links");
+        let result = self.execute_json::<UploadLinkList>(r).await?;
+        Ok(result)
+    }
+
+    /// Shows information about the upload link with the given code
+    pub async fn show_upload_link(
+        &self,
+        code: &str,
+    ) -> Result<UploadLinkInfo, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self.client.get(format!("{}/showuploadlink", self.api_host));
+
+        r = r.query(&[("code", code)]);
+
+        debug!("Requesting upload link {}", code);
+        let result = self.execute_json::<UploadLinkInfo>(r).await?;
+        Ok(result)
+    }
+
+    /// Deletes the upload link with the given id
+    pub async fn delete_upload_link(
+        &self,
+        upload_link_id: u64,
+    ) -> Result<DeleteUploadLinkResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let mut r = self
+            .client
+            .get(format!("{}/deleteuploadlink", self.api_host));
+
+        r = r.query(&[("uploadlinkid", upload_link_id)]);
+
+        debug!("Deleting upload link {}", upload_link_id);
+        let result = self.execute_json::<DeleteUploadLinkResponse>(r).await?;
+        Ok(result)
+    }
+}
@@ -1,6 +1,7 @@
 use std::io::Cursor;
 
 use chrono::DateTime;
+use futures::{StreamExt, TryStreamExt};
 use log::info;
 use pcloud_async_api::{
     self,
@@ -34,11 +35,13 @@ async fn test_event_stream() -> Result<(), Box<dyn std::error::Error + Send + Sy
 
     let pcloud = get_client().await?;
 
+    let cancellation = tokio_util::sync::CancellationToken::new();
     let mut events = pcloud
         .get_events()
         .limit(32)
         .after(&now)
         .block_timeout(Duration::from_secs(1))
+        .cancel_on(cancellation.clone())
         .stream();
 
     // Lets wait some time to avoid missed events due to times not in sync between client and server
@@ -89,11 +92,97 @@ async fn test_event_stream() -> Result<(), Box<dyn std::error::Error + Send + Sy
     assert_eq!(PCloudResult::Ok, deletefolder_result.result);
     info!("Deleted folder {}", folder_name);
 
-    // After closing the receiving event channel on has to wait long enough to run in to a timeout configured previously, otherwise there will be runtime errors droping the connection
-    sleep(Duration::from_millis(2000)).await;
+    // Check if the correct events have arrived
+    let result = rx.await?;
+    // Stop the event stream immediately, instead of waiting for the blocking request to time out
+    cancellation.cancel();
+    assert_eq!(DiffEvent::CreateFolder, result.get(0).unwrap().event);
+    assert_eq!(
+        folder_name,
+        result.get(0).unwrap().metadata.as_ref().unwrap().name
+    );
+    assert_eq!(DiffEvent::CreateFile, result.get(1).unwrap().event);
+    assert_eq!(
+        "test.txt",
+        result.get(1).unwrap().metadata.as_ref().unwrap().name
+    );
+    assert_eq!(DiffEvent::DeleteFile, result.get(2).unwrap().event);
+    assert_eq!(
+        "test.txt",
+        result.get(2).unwrap().metadata.as_ref().unwrap().name
+    );
+    assert_eq!(DiffEvent::DeleteFolder, result.get(3).unwrap().event);
+    assert_eq!(
+        folder_name,
+        result.get(3).unwrap().metadata.as_ref().unwrap().name
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_event_stream_as_futures_stream(
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Lets wait some time to avoid previous events to be shown (due to times not in sync between client and server)
+    sleep(Duration::from_millis(10000)).await;
+
+    let now = chrono::offset::Local::now();
+
+    let pcloud = get_client().await?;
+
+    let cancellation = tokio_util::sync::CancellationToken::new();
+    let events = pcloud
+        .get_events()
+        .limit(32)
+        .after(&now)
+        .block_timeout(Duration::from_secs(1))
+        .cancel_on(cancellation.clone())
+        .event_stream();
+
+    // Lets wait some time to avoid missed events due to times not in sync between client and server
+    sleep(Duration::from_millis(500)).await;
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<Vec<DiffEntry>>();
+
+    tokio::spawn(async move {
+        // 4 events: folder created, file created, file deleted, folder deleted
+        let result: Vec<DiffEntry> = events.take(4).collect().await;
+        tx.send(result).unwrap();
+    });
+
+    let folder_name = Uuid::new_v4().to_string();
+    // Create test folder
+    let createfolder_result = pcloud.create_folder("/", &folder_name)?.execute().await?;
+
+    assert_eq!(PCloudResult::Ok, createfolder_result.result);
+    assert_eq!(
+        folder_name,
+        createfolder_result.metadata.as_ref().unwrap().name
+    );
+    info!("Created test folder {}", folder_name);
+
+    // Upload file content
+    let upload_result = pcloud
+        .upload_file_into_folder(format!("/{}", folder_name))?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+
+    assert_eq!(PCloudResult::Ok, upload_result.result);
+    assert_eq!("test.txt", upload_result.metadata.get(0).unwrap().name);
+
+    // Delete test folder
+    let deletefolder_result = pcloud
+        .delete_folder(&createfolder_result.metadata.unwrap())?
+        .delete_recursive()
+        .await?;
+    assert_eq!(PCloudResult::Ok, deletefolder_result.result);
+    info!("Deleted folder {}", folder_name);
 
     // Check if the correct events have arrived
     let result = rx.await?;
+    // Stop the event stream immediately, instead of waiting for the blocking request to time out
+    cancellation.cancel();
     assert_eq!(DiffEvent::CreateFolder, result.get(0).unwrap().event);
     assert_eq!(
         folder_name,
@@ -118,6 +207,481 @@ async fn test_event_stream() -> Result<(), Box<dyn std::error::Error + Send + Sy
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_event_stream_scoped_to_folder() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+{
+    let pcloud = get_client().await?;
+
+    // Folder structure exists before streaming, so the stream has to resolve it from pCloud
+    let folder_name = Uuid::new_v4().to_string();
+    let createfolder_result = pcloud.create_folder("/", &folder_name)?.execute().await?;
+    let sub = pcloud
+        .create_folder(format!("/{}", folder_name), "sub")?
+        .execute()
+        .await?;
+    let sub_id = sub.metadata.unwrap().folderid.unwrap();
+    let deeper = pcloud.create_folder(sub_id, "deeper")?.execute().await?;
+    let deeper_id = deeper.metadata.unwrap().folderid.unwrap();
+
+    // Start right after the last event, instead of relying on the clocks of client and server
+    let last = pcloud.get_events().only_last(1).get().await?;
+
+    let cancellation = tokio_util::sync::CancellationToken::new();
+    let events = pcloud
+        .get_events()
+        .after_diff_id(last.diffid)
+        .block_timeout(Duration::from_secs(1))
+        .cancel_on(cancellation.clone())
+        .scope_to_folder(sub_id)
+        .event_stream();
+
+    // Only the second upload is within the scoped folder
+    pcloud
+        .upload_file_into_folder(format!("/{}", folder_name))?
+        .with_file("outside.txt", "This is outside")
+        .upload()
+        .await?;
+    pcloud
+        .upload_file_into_folder(deeper_id)?
+        .with_file("inside.txt", "This is inside")
+        .upload()
+        .await?;
+
+    let result: Vec<DiffEntry> =
+        tokio::time::timeout(Duration::from_secs(30), events.take(1).collect()).await?;
+    cancellation.cancel();
+
+    assert_eq!(1, result.len());
+    assert_eq!(DiffEvent::CreateFile, result[0].event);
+    assert_eq!("inside.txt", result[0].metadata.as_ref().unwrap().name);
+
+    // Delete test folder
+    pcloud
+        .delete_folder(&createfolder_result.metadata.unwrap())?
+        .delete_recursive()
+        .await?;
+    info!("Deleted folder {}", folder_name);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_event_stream_checkpoint() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let pcloud = get_client().await?;
+
+    let last = pcloud.get_events().only_last(1).get().await?;
+
+    let cancellation = tokio_util::sync::CancellationToken::new();
+    let (mut events, checkpoint) = pcloud
+        .get_events()
+        .after_diff_id(last.diffid)
+        .block_timeout(Duration::from_secs(1))
+        .cancel_on(cancellation.clone())
+        .stream_with_checkpoint();
+    let mut persisted = checkpoint.subscribe();
+
+    let folder_name = Uuid::new_v4().to_string();
+    let createfolder_result = pcloud.create_folder("/", &folder_name)?.execute().await?;
+
+    let entry = tokio::time::timeout(Duration::from_secs(30), events.recv())
+        .await?
+        .unwrap();
+    cancellation.cancel();
+    assert_eq!(DiffEvent::CreateFolder, entry.event);
+
+    // Receiving an event does not move the checkpoint, only committing it does
+    assert_eq!(last.diffid, checkpoint.current());
+    checkpoint.commit(entry.diffid);
+    assert_eq!(entry.diffid, checkpoint.current());
+    persisted.changed().await?;
+    assert_eq!(entry.diffid, *persisted.borrow());
+
+    // Older diff ids do not move the checkpoint back
+    checkpoint.commit(last.diffid);
+    assert_eq!(entry.diffid, checkpoint.current());
+
+    pcloud
+        .delete_folder(&createfolder_result.metadata.unwrap())?
+        .delete_folder_if_empty()
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_folder_size() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let createfolder_result = pcloud.create_folder("/", &folder_name)?.execute().await?;
+    let folder_id = createfolder_result.metadata.unwrap().folderid.unwrap();
+    let sub = pcloud.create_folder(folder_id, "sub")?.execute().await?;
+    let sub_id = sub.metadata.unwrap().folderid.unwrap();
+    pcloud.create_folder(sub_id, "deeper")?.execute().await?;
+
+    pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("top.txt", "top")
+        .upload()
+        .await?;
+    pcloud
+        .upload_file_into_folder(sub_id)?
+        .with_file("middle.txt", "middle")
+        .with_file("other.txt", "other")
+        .upload()
+        .await?;
+
+    let size = pcloud.folder_size(folder_id, false).await?;
+    assert_eq!(3, size.files);
+    assert_eq!(2, size.folders);
+    assert_eq!(14, size.total_bytes);
+
+    let sub_size = pcloud.folder_size(sub_id, false).await?;
+    assert_eq!(2, sub_size.files);
+    assert_eq!(1, sub_size.folders);
+    assert_eq!(11, sub_size.total_bytes);
+
+    let preview = pcloud.delete_folder(folder_id)?.preview().await?;
+    assert_eq!(Some(3), preview.deletedfiles);
+    assert_eq!(Some(3), preview.deletedfolders);
+
+    let deleted = pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+    assert_eq!(preview.deletedfiles, deleted.deletedfiles);
+    assert_eq!(preview.deletedfolders, deleted.deletedfolders);
+
+    Ok(())
+}
+
+/// Builds the metadata (as sent by pCloud) of the file (or folder) with the given id
+fn metadata_json(isfolder: bool, id: u64, name: &str) -> serde_json::Value {
+    let date = "Wed, 25 Jan 2023 12:09:14 +0000";
+    let mut metadata = serde_json::json!({
+        "parentfolderid": 0,
+        "isfolder": isfolder,
+        "ismine": true,
+        "isshared": false,
+        "name": name,
+        "id": format!("{}{}", if isfolder { "d" } else { "f" }, id),
+        "created": date,
+        "modified": date,
+        "thumb": false,
+    });
+    metadata[if isfolder { "folderid" } else { "fileid" }] = serde_json::json!(id);
+    metadata
+}
+
+/// Builds an event targeting the file (or folder) with the given id
+fn diff_entry(diffid: u64, event: &str, isfolder: bool, id: u64, name: &str) -> DiffEntry {
+    serde_json::from_value(serde_json::json!({
+        "time": "Wed, 25 Jan 2023 12:09:14 +0000",
+        "diffid": diffid,
+        "event": event,
+        "metadata": metadata_json(isfolder, id, name),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_classify_metadata() {
+    use pcloud_async_api::pcloud_model::{Entry, Metadata};
+
+    let mut folder = metadata_json(true, 5, "folder");
+    folder["contents"] = serde_json::json!([metadata_json(false, 7, "file.txt")]);
+    let folder: Metadata = serde_json::from_value(folder).unwrap();
+
+    match folder.classify().unwrap() {
+        Entry::Folder(f) => {
+            assert_eq!(5, f.folderid);
+            assert_eq!(1, f.contents.len());
+            assert!(f.contents[0].is_file());
+            assert_eq!("file.txt", f.contents[0].name());
+        }
+        Entry::File(_) => panic!("folder classified as file"),
+    }
+
+    // Missing ids are errors instead of the id of the root folder
+    let mut without_id: Metadata =
+        serde_json::from_value(metadata_json(true, 5, "folder")).unwrap();
+    without_id.folderid = None;
+    assert_eq!(
+        Some(PCloudResult::InvalidFolderId),
+        without_id.classify().err()
+    );
+
+    let mut nested = folder.clone();
+    nested.contents[0].fileid = None;
+    assert_eq!(Some(PCloudResult::InvalidFileId), nested.classify().err());
+}
+
+#[test]
+fn test_share_ownership() {
+    let metadata = |ismine: bool, isshared: bool| -> pcloud_async_api::pcloud_model::Metadata {
+        let mut metadata = metadata_json(true, 1, "folder");
+        metadata["ismine"] = serde_json::json!(ismine);
+        metadata["isshared"] = serde_json::json!(isshared);
+        serde_json::from_value(metadata).unwrap()
+    };
+
+    // Own folder, not shared
+    assert!(!metadata(true, false).is_shared_in());
+    assert!(!metadata(true, false).is_shared_out());
+    // Own folder, shared with others
+    assert!(!metadata(true, true).is_shared_in());
+    assert!(metadata(true, true).is_shared_out());
+    // Folder of another user (isshared refers to the owner's shares)
+    assert!(metadata(false, false).is_shared_in());
+    assert!(!metadata(false, false).is_shared_out());
+    assert!(metadata(false, true).is_shared_in());
+    assert!(!metadata(false, true).is_shared_out());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_list_folder_share_filters() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+    pcloud.create_folder(folder_id, "sub")?.execute().await?;
+    pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("mine.txt", "mine")
+        .upload()
+        .await?;
+
+    // Everything in a freshly created folder is owned by the user and not shared
+    let mine = pcloud.list_folder(folder_id)?.only_mine(true).get().await?;
+    assert_eq!(vec!["mine.txt", "sub"], content_names(&mine));
+    for entry in &mine.metadata.as_ref().unwrap().contents {
+        assert!(entry.ismine);
+        assert!(!entry.is_shared_in());
+        assert!(!entry.is_shared_out());
+    }
+
+    let shared = pcloud
+        .list_folder(folder_id)?
+        .only_shared(true)
+        .get()
+        .await?;
+    assert!(content_names(&shared).is_empty());
+
+    // Both filters have to match
+    let both = pcloud
+        .list_folder(folder_id)?
+        .only_mine(true)
+        .only_shared(true)
+        .get()
+        .await?;
+    assert!(content_names(&both).is_empty());
+
+    // Entries shared with the user are never owned by the user
+    let root = pcloud.list_folder(0)?.only_shared(true).get().await?;
+    for entry in &root.metadata.as_ref().unwrap().contents {
+        assert!(entry.is_shared_in() || entry.is_shared_out());
+    }
+    let root_mine = pcloud.list_folder(0)?.only_mine(true).get().await?;
+    for entry in &root_mine.metadata.as_ref().unwrap().contents {
+        assert!(!entry.is_shared_in());
+    }
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[test]
+fn test_change_set() {
+    let names = |entries: &Vec<pcloud_async_api::pcloud_model::Metadata>| -> Vec<String> {
+        entries.iter().map(|m| m.name.clone()).collect()
+    };
+
+    assert!(pcloud_async_api::events::ChangeSet::from_events(Vec::new()).is_empty());
+
+    let changes = pcloud_async_api::events::ChangeSet::from_events(vec![
+        // Created and deleted within the period: dropped
+        diff_entry(1, "createfile", false, 1, "temp.txt"),
+        // Created and modified: created with the latest metadata
+        diff_entry(2, "createfile", false, 2, "new.txt"),
+        // Modified twice: the last event wins
+        diff_entry(3, "modifyfile", false, 3, "old name.txt"),
+        diff_entry(4, "deletefile", false, 1, "temp.txt"),
+        diff_entry(5, "modifyfile", false, 2, "renamed new.txt"),
+        diff_entry(6, "modifyfile", false, 3, "new name.txt"),
+        // Modified, then deleted: deleted
+        diff_entry(7, "modifyfile", false, 4, "gone.txt"),
+        diff_entry(8, "deletefile", false, 4, "gone.txt"),
+        // Files and folders with the same id are different objects
+        diff_entry(9, "deletefolder", true, 3, "folder"),
+        diff_entry(10, "createfolder", true, 5, "created folder"),
+    ]);
+
+    assert!(!changes.is_empty());
+    assert_eq!(
+        vec!["renamed new.txt", "created folder"],
+        names(&changes.created)
+    );
+    assert_eq!(vec!["new name.txt"], names(&changes.modified));
+    assert_eq!(vec!["gone.txt", "folder"], names(&changes.deleted));
+    assert_eq!(Some(3), changes.modified[0].fileid);
+    assert_eq!(Some(3), changes.deleted[1].folderid);
+
+    // Events without file or folder metadata are ignored
+    let userinfo: DiffEntry = serde_json::from_value(serde_json::json!({
+        "time": "Wed, 25 Jan 2023 12:09:14 +0000",
+        "diffid": 11,
+        "event": "modifyuserinfo",
+    }))
+    .unwrap();
+    assert!(pcloud_async_api::events::ChangeSet::from_events(vec![userinfo]).is_empty());
+}
+
+/// Sorted names of the direct contents of a listing
+fn content_names(listing: &pcloud_async_api::pcloud_model::FileOrFolderStat) -> Vec<String> {
+    let mut names: Vec<String> = listing
+        .metadata
+        .as_ref()
+        .unwrap()
+        .contents
+        .iter()
+        .map(|m| m.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_list_folder_filters() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+    let docs_id = pcloud
+        .create_folder(folder_id, "docs")?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+    pcloud.create_folder(folder_id, "empty")?.execute().await?;
+
+    pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("report.txt", "report")
+        .with_file("report.md", "report")
+        .with_file("a1b2b3c.log", "log")
+        .with_file("Übersicht.txt", "overview")
+        .upload()
+        .await?;
+    let nested = pcloud
+        .upload_file_into_folder(docs_id)?
+        .with_file("nested.txt", "nested")
+        .with_file("nested.log", "nested")
+        .upload()
+        .await?;
+
+    // Folders are kept as long as they contain matching entries
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .recursive(true)
+        .filter_name_glob("*.txt")
+        .get()
+        .await?;
+    assert_eq!(
+        vec!["docs", "report.txt", "Übersicht.txt"],
+        content_names(&listing)
+    );
+    let docs = listing
+        .metadata
+        .as_ref()
+        .unwrap()
+        .contents
+        .iter()
+        .find(|m| m.name == "docs")
+        .unwrap();
+    assert_eq!(1, docs.contents.len());
+    assert_eq!("nested.txt", docs.contents[0].name);
+
+    // Without recursion, folders must match themselves
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .filter_name_glob("*.txt")
+        .get()
+        .await?;
+    assert_eq!(vec!["report.txt", "Übersicht.txt"], content_names(&listing));
+
+    // `?` matches exactly one character
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .filter_name_glob("report.??")
+        .get()
+        .await?;
+    assert_eq!(vec!["report.md"], content_names(&listing));
+
+    // `*` has to backtrack to find the last `b3c`
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .filter_name_glob("a*b3c.???")
+        .get()
+        .await?;
+    assert_eq!(vec!["a1b2b3c.log"], content_names(&listing));
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .filter_name_glob("a*bc*")
+        .get()
+        .await?;
+    assert!(content_names(&listing).is_empty());
+
+    // Non-ASCII characters are matched as a single character
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .filter_name_glob("?bersicht.*")
+        .get()
+        .await?;
+    assert_eq!(vec!["Übersicht.txt"], content_names(&listing));
+
+    // Categories only match files
+    let category = nested.metadata[0].category.clone().unwrap();
+    let listing = pcloud
+        .list_folder(folder_id)?
+        .recursive(true)
+        .filter_category(category.clone())
+        .get()
+        .await?;
+    let mut pending = listing.metadata.unwrap().contents;
+    let mut files = 0;
+    while let Some(entry) = pending.pop() {
+        if entry.isfolder {
+            assert!(!entry.contents.is_empty());
+            pending.extend(entry.contents);
+        } else {
+            assert_eq!(Some(&category), entry.category.as_ref());
+            files += 1;
+        }
+    }
+    assert!(files >= 1);
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_file_revisions() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let folder_name = Uuid::new_v4().to_string();
@@ -186,6 +750,22 @@ async fn test_file_revisions() -> Result<(), Box<dyn std::error::Error + Send +
         .await?;
     assert_eq!("This is nice test content", old_content_2nd);
 
+    // Multiple links at once, with isolated failures
+    let missing = format!("/{}/{}", folder_name, "missing.txt");
+    let links = pcloud
+        .get_download_links([path.as_str(), missing.as_str()])
+        .await;
+    assert_eq!(2, links.len());
+    assert!(links[0].is_ok());
+    assert!(links[1].is_err());
+
+    // Revert to old rev
+    let revert_result = pcloud.revert_file(path.as_str(), rev_id).await?;
+    assert_eq!(PCloudResult::Ok, revert_result.result);
+
+    let reverted_content = pcloud.download_file(path.as_str()).await?.text().await?;
+    assert_eq!("This is nice test content", reverted_content);
+
     // Delete test folder
     let deletefolder_result = pcloud
         .delete_folder(&createfolder_result.metadata.unwrap())?
@@ -222,6 +802,7 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         .upload_file_into_folder(format!("/{}", folder_name))?
         .rename_if_exists(false)
         .mtime(&date)
+        .request_checksums(true)
         .with_file("test.txt", "This is nice test content")
         .with_file("second test.txt", "This is another nice test content")
         .upload()
@@ -237,8 +818,16 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
     );
     assert_eq!(date, upload_result.metadata.get(0).unwrap().modified);
     assert_eq!(date, upload_result.metadata.get(1).unwrap().modified);
-    info!("Created test files: {:?}", upload_result.fileids);
-
+    assert_eq!(vec!["test.txt", "second test.txt"], upload_result.names());
+    assert_eq!(
+        ("This is nice test content".len() + "This is another nice test content".len()) as u64,
+        upload_result.total_size()
+    );
+    for (file_id, metadata) in upload_result.fileids.iter().zip(&upload_result.metadata) {
+        assert_eq!(Some(*file_id), metadata.fileid);
+    }
+    info!("Created test files: {:?}", upload_result.fileids);
+
     let file_id = upload_result.fileids.get(0).unwrap();
     let file_id2 = upload_result.fileids.get(1).unwrap();
 
@@ -259,10 +848,30 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         metadata.metadata.as_ref().unwrap().name
     );
 
+    // Accessors of the metadata
+    assert_eq!(Some("test.txt"), metadata.name());
+    assert_eq!(Some(*file_id), metadata.file_id());
+    assert_eq!("test.txt", metadata.metadata_or_err()?.name);
+
+    // Update the file times
+    let touched_date =
+        DateTime::parse_from_str("2023 Feb 14 08:30:00.000 +0000", "%Y %b %d %H:%M:%S%.3f %z")
+            .unwrap();
+    let touch_result = pcloud
+        .set_file_times(file_id2, &touched_date, Some(&date))
+        .await?;
+    assert_eq!(Some(*file_id2), touch_result.file_id());
+    assert_eq!(touched_date, touch_result.metadata_or_err()?.modified);
+    assert_eq!(date, touch_result.metadata_or_err()?.created);
+    let touched = pcloud.download_file(file_id2).await?.text().await?;
+    assert_eq!("This is another nice test content", touched);
+    info!("Updated file times");
+
     // Copy one file
     let copy_result = pcloud
         .copy_file(file_id, format!("/{}/anothertext.txt", folder_name))?
         .overwrite(true)
+        .preserve_timestamps(true)
         .execute()
         .await?;
     assert_eq!(PCloudResult::Ok, copy_result.result);
@@ -270,6 +879,7 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         "anothertext.txt",
         copy_result.metadata.as_ref().unwrap().name
     );
+    assert_eq!(date, copy_result.metadata.as_ref().unwrap().modified);
 
     let d1 = pcloud
         .download_file(copy_result.metadata.as_ref().unwrap())
@@ -283,6 +893,61 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         copy_result.metadata.as_ref().unwrap().name
     );
 
+    // Copy into a folder keeping the name (path ending with /), to a folder path with a new name and to a folder id with a new name
+    pcloud
+        .create_folder(format!("/{}", folder_name), "copies")?
+        .execute()
+        .await?;
+    let copy_result = pcloud
+        .copy_file(file_id, format!("/{}/copies/", folder_name))?
+        .execute()
+        .await?;
+    assert_eq!(Some("test.txt"), copy_result.name());
+    let copy_result = pcloud
+        .copy_file(file_id, format!("/{}/copies", folder_name))?
+        .with_new_name("renamed.txt")
+        .execute()
+        .await?;
+    assert_eq!(Some("renamed.txt"), copy_result.name());
+    let copy_result = pcloud
+        .copy_file(
+            file_id,
+            copy_result.metadata_or_err()?.parentfolderid.unwrap(),
+        )?
+        .with_new_name("by id.txt")
+        .execute()
+        .await?;
+    assert_eq!(Some("by id.txt"), copy_result.name());
+
+    // Malformed targets are rejected before contacting pCloud
+    assert!(pcloud
+        .copy_file(file_id, format!("/{}/copies", folder_name))?
+        .with_new_name("")
+        .execute()
+        .await
+        .is_err());
+    assert!(pcloud
+        .copy_file(file_id, format!("/{}/copies", folder_name))?
+        .with_new_name("sub/name.txt")
+        .execute()
+        .await
+        .is_err());
+    assert!(pcloud
+        .copy_file(
+            file_id,
+            pcloud_async_api::folder_ops::PCloudFolder {
+                folder_id: None,
+                path: None,
+                parent_folder_id: None,
+                name: Some("orphan.txt".to_string()),
+            }
+        )
+        .is_err());
+    pcloud
+        .delete_folder(format!("/{}/copies", folder_name))?
+        .delete_recursive()
+        .await?;
+
     // Move one file
     let move_result = pcloud
         .move_file(file_id2, format!("/{}/third test.txt", folder_name))?
@@ -293,6 +958,7 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         "third test.txt",
         move_result.metadata.as_ref().unwrap().name
     );
+    assert_eq!(None, move_result.overwrote_file_id());
 
     let d2 = pcloud
         .download_file(move_result.metadata.as_ref().unwrap())
@@ -309,8 +975,45 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
     // Fetch checksums
     let checksum_result = pcloud.checksum_file(file_id)?.get().await?;
     assert_eq!(PCloudResult::Ok, checksum_result.result);
+    let (kind, digest) = checksum_result.best().unwrap();
+    assert_ne!(pcloud_async_api::pcloud_model::DigestKind::Md5, kind);
+    assert_eq!(Some(digest), checksum_result.get(kind));
+    assert_eq!(2, upload_result.checksums.len());
+    assert_eq!(checksum_result.sha1, upload_result.checksums[0].sha1);
+
+    // Uploading the unchanged file again is skipped, the new file is uploaded
+    let reupload_result = pcloud
+        .upload_file_into_folder(format!("/{}", folder_name))?
+        .with_file("test.txt", "This is nice test content")
+        .skip_if_unchanged(checksum_result.sha1.as_ref().unwrap())
+        .with_file("fourth test.txt", "This is new content")
+        .skip_if_unchanged(checksum_result.sha1.as_ref().unwrap())
+        .upload()
+        .await?;
+    assert_eq!(2, reupload_result.fileids.len());
+    assert_eq!(*file_id, reupload_result.fileids[0]);
+    assert_eq!(vec!["test.txt", "fourth test.txt"], reupload_result.names());
+    pcloud.delete_file(reupload_result.fileids[1]).await?;
     info!("Fetched checksums");
 
+    // Upload from an async reader
+    let reader_result = pcloud
+        .upload_file_into_folder(format!("/{}", folder_name))?
+        .with_async_read(
+            "streamed.txt",
+            Cursor::new(b"This is streamed content".to_vec()),
+        )
+        .upload()
+        .await?;
+    assert_eq!(vec!["streamed.txt"], reader_result.names());
+    let streamed = pcloud
+        .download_file(reader_result.fileids[0])
+        .await?
+        .text()
+        .await?;
+    assert_eq!("This is streamed content", streamed);
+    pcloud.delete_file(reader_result.fileids[0]).await?;
+
     sleep(Duration::from_millis(500)).await;
 
     // List folder content
@@ -330,6 +1033,11 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
         .collect();
 
     assert_eq!(3, files.len());
+    let direct = pcloud
+        .list_folder(createfolder_result.metadata.as_ref().unwrap())?
+        .count_only()
+        .await?;
+    assert_eq!(folder_content.entry_count(), direct);
     assert_eq!(true, files.contains(&String::from("test.txt")));
     assert_eq!(true, files.contains(&String::from("third test.txt")));
     assert_eq!(true, files.contains(&String::from("anothertext.txt")));
@@ -337,11 +1045,35 @@ async fn test_file_operations() -> Result<(), Box<dyn std::error::Error + Send +
     assert_eq!(false, files.contains(&String::from("second test.txt")));
     info!("Listed folder content: {:?}", files);
 
+    let mut tree = folder_content.metadata.unwrap();
+    tree.resolve_paths(&format!("/{}", folder_name));
+    assert!(tree
+        .contents
+        .iter()
+        .any(|m| m.path == Some(format!("/{}/test.txt", folder_name))));
+
     // Delete test file
     let delete_result = pcloud.delete_file(file_id).await?;
     assert_eq!(PCloudResult::Ok, delete_result.result);
     info!("Deleted file {}", delete_result.metadata.unwrap().name);
 
+    // Delete another file, reporting whether it was moved to the trash (depends on the account settings)
+    let detailed = pcloud
+        .delete_file_detailed(format!("/{}/anothertext.txt", folder_name))
+        .await?;
+    let deleted = detailed.metadata.unwrap();
+    assert_eq!("anothertext.txt", deleted.name);
+    let in_trash = match pcloud
+        .stat_file(deleted.fileid.unwrap())?
+        .show_deleted(true)
+        .get()
+        .await
+    {
+        Ok(stat) => stat.metadata.and_then(|m| m.isdeleted).unwrap_or(false),
+        Err(_) => false,
+    };
+    assert_eq!(in_trash, detailed.trashed);
+
     // Delete test folder
     let deletefolder_result = pcloud
         .delete_folder(&createfolder_result.metadata.unwrap())?
@@ -447,6 +1179,46 @@ async fn test_zip_download() -> Result<(), Box<dyn std::error::Error + Send + Sy
         }
     }
 
+    // Already resolved ids are added without awaiting, the second file is resolved from its path
+    let bytes = pcloud
+        .download_zip_of_files(
+            pcloud
+                .create_tree()
+                .add_files([*file_id])
+                .add_file_path(&format!("/{}/second test.txt", folder_name)),
+        )
+        .await?
+        .bytes()
+        .await?;
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(2, archive.len());
+
+    // Zip everything listed in the folder
+    let listing = pcloud
+        .list_folder(format!("/{}", folder_name))?
+        .get()
+        .await?;
+    let bytes = pcloud
+        .download_zip_of_files(pcloud.create_tree_from_listing(&listing))
+        .await?
+        .bytes()
+        .await?;
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(2, archive.len());
+
+    // Download the zip again as stream and track the progress
+    let (stream, mut progress) = pcloud
+        .download_zip_with_progress(pcloud.create_tree().add_files([*file_id, *file_id2]))
+        .await?;
+    let chunks: Vec<bytes::Bytes> = stream.try_collect().await?;
+    let total: u64 = chunks.iter().map(|c| c.len() as u64).sum();
+    let mut last = 0;
+    while let Ok(downloaded) = progress.try_recv() {
+        assert!(downloaded > last);
+        last = downloaded;
+    }
+    assert!(last > 0 && last <= total);
+
     // Delete test folder
     let deletefolder_result = pcloud
         .delete_folder(&createfolder_result.metadata.unwrap())?
@@ -457,3 +1229,1095 @@ async fn test_zip_download() -> Result<(), Box<dyn std::error::Error + Send + Sy
 
     Ok(())
 }
+
+#[cfg(feature = "remote_zip")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_save_zip() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("test.txt", "This is nice test content")
+        .with_file("second test.txt", "This is another nice test content")
+        .upload()
+        .await?;
+
+    // Zip both files into the test folder
+    let zip_result = pcloud
+        .create_zip(
+            pcloud
+                .create_tree()
+                .add_files(upload_result.fileids.clone()),
+        )
+        .to_folder(folder_id, "archive.zip")?
+        .execute()
+        .await?;
+    assert_eq!(PCloudResult::Ok, zip_result.result);
+
+    let stat = pcloud
+        .get_file_metadata(format!("/{}/archive.zip", folder_name))
+        .await?;
+    assert_eq!(Some("archive.zip"), stat.name());
+
+    let bytes = pcloud.read_file_bytes(stat.metadata_or_err()?).await?;
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(2, archive.len());
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_path_cache() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+    let folder_path = format!("/{}", folder_name);
+    let file_path = format!("{}/test.txt", folder_path);
+
+    let pcloud = get_client().await?.with_path_cache(16);
+
+    let createfolder_result = pcloud.create_folder("/", &folder_name)?.execute().await?;
+    assert_eq!(PCloudResult::Ok, createfolder_result.result);
+    let folder_id = createfolder_result.metadata.unwrap().folderid.unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+    let file_id = *upload_result.fileids.get(0).unwrap();
+
+    // Nothing is cached before a path is resolved for the first time
+    assert_eq!(None, pcloud.cached_file_id(&file_path));
+    assert_eq!(None, pcloud.cached_folder_id(&folder_path));
+
+    // Resolving the paths fills the cache
+    pcloud
+        .create_tree()
+        .with_file(file_path.as_str())
+        .await?
+        .with_folder(folder_path.as_str())
+        .await?;
+    assert_eq!(Some(file_id), pcloud.cached_file_id(&file_path));
+    assert_eq!(Some(folder_id), pcloud.cached_folder_id(&folder_path));
+
+    // Moving the file invalidates the cached path
+    let moved_path = format!("{}/moved.txt", folder_path);
+    pcloud
+        .move_file(file_path.as_str(), folder_id)?
+        .with_new_name("moved.txt")
+        .execute()
+        .await?;
+    assert_eq!(None, pcloud.cached_file_id(&file_path));
+    assert_eq!(Some(folder_id), pcloud.cached_folder_id(&folder_path));
+
+    pcloud.create_tree().with_file(moved_path.as_str()).await?;
+    assert_eq!(Some(file_id), pcloud.cached_file_id(&moved_path));
+
+    // Delete the file behind the back of the caching client. A cached path is resolved without asking the server, so resolving it still succeeds.
+    let other = get_client().await?;
+    other.delete_file(file_id).await?;
+    pcloud.create_tree().with_file(moved_path.as_str()).await?;
+    assert_eq!(Some(file_id), pcloud.cached_file_id(&moved_path));
+
+    // Deleting the folder invalidates the folder and everything below it
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+    assert_eq!(None, pcloud.cached_folder_id(&folder_path));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_copy_folder_merge() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let source_id = pcloud
+        .create_folders_recursive(&format!("/{}/source/sub", folder_name))
+        .await?
+        .1
+        .parentfolderid
+        .unwrap();
+    let (target_id, _) = pcloud
+        .create_folders_recursive(&format!("/{}/target", folder_name))
+        .await?;
+
+    pcloud
+        .upload_file_into_folder(source_id)?
+        .with_file("new.txt", "This is nice test content")
+        .with_file("existing.txt", "This is new content")
+        .upload()
+        .await?;
+    pcloud
+        .upload_file_into_folder(format!("/{}/source/sub", folder_name))?
+        .with_file("deep.txt", "This is deep content")
+        .upload()
+        .await?;
+    pcloud
+        .upload_file_into_folder(target_id)?
+        .with_file("existing.txt", "This is old content")
+        .with_file("untouched.txt", "This stays")
+        .upload()
+        .await?;
+
+    // Without overwrite nothing is copied
+    assert!(pcloud
+        .copy_folder_merge(source_id, target_id)?
+        .overwrite(false)
+        .execute()
+        .await
+        .is_err());
+
+    // Skipping keeps the existing file
+    let merged = pcloud
+        .copy_folder_merge(source_id, target_id)?
+        .skip_existing(true)
+        .execute()
+        .await?;
+    assert_eq!(2, merged.copied.len());
+    assert_eq!(1, merged.skipped.len());
+    let content = pcloud
+        .read_file_bytes(format!("/{}/target/existing.txt", folder_name))
+        .await?;
+    assert_eq!("This is old content".as_bytes(), content.as_slice());
+
+    // Overwriting replaces it, files only present in the target are kept
+    let (handle, _progress) = pcloud
+        .copy_folder_merge(source_id, target_id)?
+        .execute_with_progress();
+    let merged = handle.await??;
+    assert_eq!(3, merged.copied.len());
+    assert_eq!(0, merged.folders);
+    let content = pcloud
+        .read_file_bytes(format!("/{}/target/existing.txt", folder_name))
+        .await?;
+    assert_eq!("This is new content".as_bytes(), content.as_slice());
+    assert!(
+        pcloud
+            .file_exists(format!("/{}/target/untouched.txt", folder_name))
+            .await?
+    );
+    assert!(
+        pcloud
+            .file_exists(format!("/{}/target/sub/deep.txt", folder_name))
+            .await?
+    );
+
+    // Folders given by parent id and name are resolved first
+    let (root_id, _) = pcloud.resolve_folder(format!("/{}", folder_name)).await?;
+    let merged = pcloud
+        .copy_folder_merge(
+            pcloud_async_api::folder_ops::PCloudFolder::child(root_id, "source"),
+            pcloud_async_api::folder_ops::PCloudFolder::child(root_id, "target"),
+        )?
+        .skip_existing(true)
+        .execute()
+        .await?;
+    assert_eq!(target_id, merged.folder_id);
+    assert_eq!(0, merged.copied.len());
+    assert_eq!(3, merged.skipped.len());
+
+    pcloud
+        .delete_folder(format!("/{}", folder_name))?
+        .delete_recursive()
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_copy_folder_flags() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let root_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+    let source_id = pcloud
+        .create_folder(root_id, "source")?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+    let target_id = pcloud
+        .create_folder(root_id, "target")?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    pcloud
+        .upload_file_into_folder(source_id)?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+
+    let names = |stat: pcloud_async_api::pcloud_model::FileOrFolderStat| -> Vec<String> {
+        let mut names: Vec<String> = stat
+            .metadata
+            .unwrap()
+            .contents
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        names.sort();
+        names
+    };
+
+    // Only the content of the source folder is copied
+    pcloud
+        .copy_folder(source_id, target_id)?
+        .copy_content_only(true)
+        .execute()
+        .await?;
+    let target = pcloud.list_folder(target_id)?.get().await?;
+    assert_eq!(vec!["test.txt".to_string()], names(target));
+
+    // Existing files fail the copy without overwriting ...
+    assert!(pcloud
+        .copy_folder(source_id, target_id)?
+        .overwrite(false)
+        .copy_content_only(true)
+        .execute()
+        .await
+        .is_err());
+
+    // ... unless they are skipped
+    let copy_result = pcloud
+        .copy_folder(source_id, target_id)?
+        .overwrite(false)
+        .skip_existing(true)
+        .copy_content_only(true)
+        .execute()
+        .await?;
+    assert_eq!(PCloudResult::Ok, copy_result.result);
+
+    // Without the flags the folder itself is copied
+    pcloud.copy_folder(source_id, target_id)?.execute().await?;
+    let target = pcloud.list_folder(target_id)?.get().await?;
+    assert_eq!(
+        vec!["source".to_string(), "test.txt".to_string()],
+        names(target)
+    );
+
+    pcloud.delete_folder(root_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[test]
+fn test_copy_folder_flags_query() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let flags = |skip_existing: bool, copy_content_only: bool| {
+        let r = reqwest::Client::new().post("https://api.pcloud.com/copyfolder");
+        let r = pcloud_async_api::folder_ops::CopyFolderRequestBuilder::add_flags(
+            r,
+            true,
+            skip_existing,
+            copy_content_only,
+        );
+        r.build().map(|r| {
+            let mut pairs: Vec<(String, String)> = r.url().query_pairs().into_owned().collect();
+            pairs.sort();
+            pairs
+        })
+    };
+    let pair = |k: &str| (k.to_string(), "1".to_string());
+
+    assert_eq!(Vec::<(String, String)>::new(), flags(false, false)?);
+    assert_eq!(vec![pair("skipexisting")], flags(true, false)?);
+    assert_eq!(vec![pair("copycontentonly")], flags(false, true)?);
+    assert_eq!(
+        vec![pair("copycontentonly"), pair("skipexisting")],
+        flags(true, true)?
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "low_level_file_ops")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_open_file_read_only() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+
+    // Opening read-only requires no flags at all
+    let file = pcloud
+        .open_file()
+        .by_file_in_folder(folder_id, "test.txt")
+        .await?
+        .read_only()
+        .open()
+        .await?;
+    let close_result = file.close().await?;
+    assert_eq!(PCloudResult::Ok, close_result.result);
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_read_lines() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file(
+            "lines.txt",
+            "first line\r\nzweite Zeile mit Ümlauten\n\nlast line",
+        )
+        .upload()
+        .await?;
+    let file_id = *upload_result.fileids.get(0).unwrap();
+
+    let bytes = pcloud.read_file_bytes(file_id).await?;
+    assert_eq!(49, bytes.len());
+    assert!(pcloud.read_file_bytes_limited(file_id, 10).await.is_err());
+
+    let lines: Vec<String> = pcloud.read_lines(file_id).await?.try_collect().await?;
+    assert_eq!(
+        vec![
+            "first line".to_string(),
+            "zweite Zeile mit Ümlauten".to_string(),
+            "".to_string(),
+            "last line".to_string()
+        ],
+        lines
+    );
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_upload_fail_if_exists() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .fail_if_exists(true)
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+    let file_id = *upload_result.fileids.get(0).unwrap();
+
+    let second_upload = pcloud
+        .upload_file_into_folder(folder_id)?
+        .fail_if_exists(true)
+        .with_file("test.txt", "This would overwrite the content")
+        .upload()
+        .await;
+    let err = second_upload.unwrap_err();
+    let already_exists = err
+        .downcast_ref::<pcloud_async_api::pcloud_model::AlreadyExistsError>()
+        .unwrap();
+    assert_eq!(Some(file_id), already_exists.metadata.fileid);
+
+    let content = pcloud.download_file(file_id).await?.text().await?;
+    assert_eq!("This is nice test content", content);
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_uploads() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    // Upload files in parallel from copies of the same client
+    let uploads = (0..8).map(|i| {
+        move |client: pcloud_async_api::pcloud_client::PCloudClient| async move {
+            let result = client
+                .upload_file_into_folder(folder_id)?
+                .with_file(&format!("file-{}.txt", i), format!("Content of file {}", i))
+                .upload()
+                .await?;
+            Ok::<u64, Box<dyn std::error::Error + Send + Sync>>(*result.fileids.get(0).unwrap())
+        }
+    });
+    let file_ids = pcloud
+        .spawn_concurrent(uploads, 4)
+        .await
+        .into_iter()
+        .collect::<Result<Vec<u64>, _>>()?;
+    assert_eq!(8, file_ids.len());
+
+    // Download them in parallel again, results are in the order of the tasks
+    let downloads = file_ids.iter().map(|file_id| {
+        let file_id = *file_id;
+        move |client: pcloud_async_api::pcloud_client::PCloudClient| async move {
+            let content = client.download_file(file_id).await?.text().await?;
+            Ok::<String, Box<dyn std::error::Error + Send + Sync>>(content)
+        }
+    });
+    let contents = pcloud.spawn_concurrent(downloads, 4).await;
+    for (i, content) in contents.into_iter().enumerate() {
+        assert_eq!(format!("Content of file {}", i), content?);
+    }
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_dry_run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client()
+        .await?
+        .with_request_logging(true)
+        .with_user_agent("pcloud-async-api-test");
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+    let file_id = *upload_result.fileids.get(0).unwrap();
+
+    // Dry-run mode is shared between all copies of the client
+    let dry = pcloud.clone();
+    dry.dry_run(true);
+    assert!(pcloud.is_dry_run());
+
+    let delete_result = pcloud.delete_file(file_id).await?;
+    assert_eq!(PCloudResult::Ok, delete_result.result);
+    let delete_result = pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+    assert_eq!(PCloudResult::Ok, delete_result.result);
+
+    // Preview what a recursive delete would remove
+    let preview = pcloud.delete_folder(folder_id)?.preview().await?;
+    assert_eq!(Some(1), preview.deletedfiles);
+    assert_eq!(Some(1), preview.deletedfolders);
+
+    // Nothing was actually deleted
+    pcloud.dry_run(false);
+    let stat = pcloud.get_file_metadata(file_id).await?;
+    assert_eq!(PCloudResult::Ok, stat.result);
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_upload_with_mime() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    // Invalid MIME types are rejected before uploading
+    assert!(pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file_mime("test.json", "{}", "not a mime type")
+        .is_err());
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file_mime("test.json", "{\"key\": \"value\"}", "application/json")?
+        .upload()
+        .await?;
+    assert_eq!(PCloudResult::Ok, upload_result.result);
+    assert_eq!(1, upload_result.fileids.len());
+
+    // The parent of the uploaded file is the test folder, whose parent is the root folder, which has no parent
+    let parent = pcloud
+        .parent_of(upload_result.metadata.get(0).unwrap())
+        .await?
+        .unwrap()
+        .metadata
+        .unwrap();
+    assert_eq!(Some(folder_id), parent.folderid);
+    let root = pcloud.parent_of(&parent).await?.unwrap().metadata.unwrap();
+    assert_eq!(Some(0), root.folderid);
+    assert!(pcloud.parent_of(&root).await?.is_none());
+
+    assert!(
+        pcloud
+            .file_exists(*upload_result.fileids.get(0).unwrap())
+            .await?
+    );
+    assert!(
+        !pcloud
+            .file_exists(format!("/{}/missing.json", folder_name))
+            .await?
+    );
+    assert!(pcloud.folder_exists(folder_id).await?);
+    assert!(
+        !pcloud
+            .folder_exists(format!("/{}/missing", folder_name))
+            .await?
+    );
+
+    let (resolved_id, resolved) = pcloud.resolve_folder(format!("/{}", folder_name)).await?;
+    assert_eq!(resolved_id, folder_id);
+    assert_eq!(resolved.name, folder_name);
+
+    let (nested_id, nested) = pcloud
+        .create_folders_recursive(&format!("/{}/a/b/c", folder_name))
+        .await?;
+    assert_eq!(nested.name, "c");
+    let uploaded = pcloud
+        .upload_file_into_folder(format!("/{}/a/b/c/d", folder_name))?
+        .mkdirs(true)
+        .with_file("mkdirs.txt", "mkdirs")
+        .upload()
+        .await?;
+    let d = pcloud.parent_of(&uploaded.metadata[0]).await?.unwrap();
+    assert_eq!(d.metadata.unwrap().parentfolderid, Some(nested_id));
+
+    let (handle, mut progress) = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("progress.txt", "uploaded with progress")
+        .upload_with_progress_notification(Duration::from_millis(100));
+    let uploaded = handle.await??;
+    assert_eq!(1, uploaded.metadata.len());
+    while let Some(p) = progress.recv().await {
+        assert!(p.uploaded <= p.total || p.total == 0);
+    }
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_move_folder_conflicts() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+    let root = format!("/{}", folder_name);
+
+    let pcloud = get_client().await?;
+    pcloud.create_folder("/", &folder_name)?.execute().await?;
+
+    // Source: /a/sub/source.txt, /a/new/new.txt and /a/both.txt
+    // Target: /target/a/sub/target.txt, /target/a/kept/ and /target/a/both.txt
+    for path in [
+        "a",
+        "a/sub",
+        "a/new",
+        "target",
+        "target/a",
+        "target/a/sub",
+        "target/a/kept",
+    ] {
+        let (parent, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (format!("{}/{}", root, parent), name),
+            None => (root.clone(), path),
+        };
+        pcloud.create_folder(parent, name)?.execute().await?;
+    }
+    for (folder, file, content) in [
+        ("a/sub", "source.txt", "source"),
+        ("a/new", "new.txt", "new"),
+        ("a", "both.txt", "from source"),
+        ("target/a/sub", "target.txt", "target"),
+        ("target/a", "both.txt", "from target"),
+    ] {
+        pcloud
+            .upload_file_into_folder(format!("{}/{}", root, folder))?
+            .with_file(file, content)
+            .upload()
+            .await?;
+    }
+
+    let names = |stat: pcloud_async_api::pcloud_model::FileOrFolderStat| -> Vec<String> {
+        let mut names: Vec<String> = stat
+            .metadata
+            .unwrap()
+            .contents
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        names.sort();
+        names
+    };
+
+    // Without conflict handling pCloud refuses to move the folder
+    assert!(pcloud
+        .move_folder(format!("{}/a", root), format!("{}/target/", root))?
+        .execute()
+        .await
+        .is_err());
+
+    // Merging moves the contents recursively
+    pcloud
+        .move_folder(format!("{}/a", root), format!("{}/target/", root))?
+        .on_conflict(pcloud_async_api::folder_ops::MoveConflict::Merge)
+        .execute()
+        .await?;
+
+    assert!(!pcloud.folder_exists(format!("{}/a", root)).await?);
+    assert_eq!(
+        vec!["both.txt", "kept", "new", "sub"],
+        names(
+            pcloud
+                .list_folder(format!("{}/target/a", root))?
+                .get()
+                .await?
+        )
+    );
+    assert_eq!(
+        vec!["source.txt", "target.txt"],
+        names(
+            pcloud
+                .list_folder(format!("{}/target/a/sub", root))?
+                .get()
+                .await?
+        )
+    );
+    let both = pcloud
+        .download_file(format!("{}/target/a/both.txt", root))
+        .await?
+        .text()
+        .await?;
+    assert_eq!("from source", both);
+
+    // Client-side sorting, folders before files
+    let sorted: Vec<String> = pcloud
+        .list_folder(format!("{}/target/a", root))?
+        .sort_by(pcloud_async_api::folder_ops::SortKey::Name)
+        .sort_dir(pcloud_async_api::folder_ops::SortDirection::Desc)
+        .folders_first(true)
+        .get()
+        .await?
+        .metadata
+        .unwrap()
+        .contents
+        .into_iter()
+        .map(|m| m.name)
+        .collect();
+    assert_eq!(vec!["sub", "new", "kept", "both.txt"], sorted);
+
+    // Replacing deletes the existing folder first
+    pcloud.create_folder(root.clone(), "a")?.execute().await?;
+    pcloud
+        .upload_file_into_folder(format!("{}/a", root))?
+        .with_file("replacement.txt", "replacement")
+        .upload()
+        .await?;
+    pcloud
+        .move_folder(format!("{}/a", root), format!("{}/target/", root))?
+        .on_conflict(pcloud_async_api::folder_ops::MoveConflict::Replace)
+        .execute()
+        .await?;
+    assert_eq!(
+        vec!["replacement.txt"],
+        names(
+            pcloud
+                .list_folder(format!("{}/target/a", root))?
+                .get()
+                .await?
+        )
+    );
+
+    // If the move fails (here: a folder cannot be moved into itself), the existing folder is kept
+    pcloud
+        .create_folder(format!("{}/target/a", root), "inner")?
+        .execute()
+        .await?;
+    pcloud
+        .create_folder(format!("{}/target/a/inner", root), "a")?
+        .execute()
+        .await?;
+    assert!(pcloud
+        .move_folder(
+            format!("{}/target/a", root),
+            format!("{}/target/a/inner/", root)
+        )?
+        .on_conflict(pcloud_async_api::folder_ops::MoveConflict::Replace)
+        .execute()
+        .await
+        .is_err());
+    assert!(
+        pcloud
+            .folder_exists(format!("{}/target/a/inner/a", root))
+            .await?
+    );
+    assert_eq!(
+        vec!["inner", "replacement.txt"],
+        names(
+            pcloud
+                .list_folder(format!("{}/target/a", root))?
+                .get()
+                .await?
+        )
+    );
+
+    pcloud
+        .delete_folder(root.as_str())?
+        .delete_recursive()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "verify_checksum")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_download_verified() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folder("/", &folder_name)?
+        .execute()
+        .await?
+        .metadata
+        .unwrap()
+        .folderid
+        .unwrap();
+
+    let upload_result = pcloud
+        .upload_file_into_folder(folder_id)?
+        .with_file("test.txt", "This is nice test content")
+        .upload()
+        .await?;
+    let file_id = *upload_result.fileids.get(0).unwrap();
+
+    let mut content: Vec<u8> = Vec::new();
+    let written = pcloud.download_verified_to(file_id, &mut content).await?;
+    assert_eq!(25, written);
+    assert_eq!("This is nice test content".as_bytes(), content.as_slice());
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "streaming_listing")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_streaming_listing() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    let pcloud = get_client().await?;
+
+    let folder_id = pcloud
+        .create_folders_recursive(&format!("/{}/sub", folder_name))
+        .await?
+        .1
+        .parentfolderid
+        .unwrap();
+
+    pcloud
+        .upload_file_into_folder(format!("/{}/sub", folder_name))?
+        .with_file("test.txt", "This is nice test content")
+        .with_file("second test.txt", "This is another nice test content")
+        .upload()
+        .await?;
+
+    let mut rx = pcloud
+        .list_folder(folder_id)?
+        .recursive(true)
+        .stream()
+        .await?;
+    let mut names = Vec::new();
+    while let Some(entry) = rx.recv().await {
+        let entry = entry?;
+        assert!(entry.contents.is_empty());
+        names.push(entry.name);
+    }
+    names.sort();
+    assert_eq!(vec!["second test.txt", "sub", "test.txt"], names);
+
+    // Errors are sent as last entry
+    let mut rx = pcloud
+        .list_folder(format!("/{}/missing", folder_name))?
+        .stream()
+        .await?;
+    assert!(rx.recv().await.unwrap().is_err());
+    assert!(rx.recv().await.is_none());
+
+    pcloud.delete_folder(folder_id)?.delete_recursive().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_compressed_responses() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let host = std::env::var("PCLOUD_HOST")?;
+    let user = std::env::var("PCLOUD_USER")?;
+    let pw = std::env::var("PCLOUD_PASSWORD")?;
+
+    let pcloud = pcloud_async_api::pcloud_client::PCloudClient::builder()
+        .accept_compressed(true)
+        .user_agent("pcloud-async-api integration test")
+        .login_with_password(&host, &user, &pw)
+        .await?;
+
+    // Compressed listings are decompressed transparently
+    let compressed = pcloud.list_folder(0)?.get().await?;
+    let plain = get_client().await?.list_folder(0)?.get().await?;
+    assert_eq!(PCloudResult::Ok, compressed.result);
+    assert_eq!(
+        plain.metadata_or_err()?.contents.len(),
+        compressed.metadata_or_err()?.contents.len()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_logout() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let pcloud = get_client().await?;
+    let copy = pcloud.clone();
+
+    let result = pcloud.logout().await?;
+    assert_eq!(Some(true), result.auth_deleted);
+
+    // The session is shared, so the copy is logged out, too
+    assert!(copy.get_user_info().await.is_err());
+    let result = copy.logout().await?;
+    assert_eq!(Some(false), result.auth_deleted);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_drop_session_in_task() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Dropping the last copy of a session within a task on the only worker thread must not dead-lock the logout
+    let dropped = tokio::spawn(async move {
+        let pcloud = get_client().await?;
+        pcloud.get_user_info().await?;
+        drop(pcloud);
+        Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+    });
+
+    tokio::time::timeout(Duration::from_secs(30), dropped).await???;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_upload_directory() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let folder_name = Uuid::new_v4().to_string();
+
+    // Local directory with a nested structure
+    let local_dir = std::env::temp_dir().join(&folder_name);
+    std::fs::create_dir_all(local_dir.join("sub").join("deeper"))?;
+    std::fs::write(local_dir.join("top.txt"), "top")?;
+    std::fs::write(local_dir.join("sub").join("middle.txt"), "middle")?;
+    std::fs::write(
+        local_dir.join("sub").join("deeper").join("bottom.txt"),
+        "bottom",
+    )?;
+
+    let pcloud = get_client().await?;
+    pcloud.create_folder("/", &folder_name)?.execute().await?;
+
+    let (handle, mut progress) = pcloud
+        .upload_directory(format!("/{}", folder_name), &local_dir)?
+        .concurrency(2)
+        .upload_with_progress();
+    let result = handle.await??;
+
+    assert_eq!(2, result.folders);
+    assert_eq!(3, result.files.len());
+    assert_eq!(15, result.total_size());
+
+    let mut last = None;
+    while let Some(p) = progress.recv().await {
+        last = Some(p);
+    }
+    let last = last.unwrap();
+    assert_eq!(3, last.files_total);
+    assert_eq!(15, last.bytes_total);
+    assert_eq!(3, last.files_done);
+
+    let bottom = pcloud
+        .download_file(format!("/{}/sub/deeper/bottom.txt", folder_name))
+        .await?
+        .text()
+        .await?;
+    assert_eq!("bottom", bottom);
+
+    assert!(pcloud.find_duplicates(result.folder_id).await?.is_empty());
+    pcloud
+        .copy_file(
+            format!("/{}/top.txt", folder_name),
+            format!("/{}/sub/copy.txt", folder_name),
+        )?
+        .execute()
+        .await?;
+    let duplicates = pcloud.find_duplicates(result.folder_id).await?;
+    assert_eq!(1, duplicates.len());
+    assert_eq!(2, duplicates.values().next().unwrap().len());
+
+    pcloud
+        .delete_folder(format!("/{}", folder_name))?
+        .delete_recursive()
+        .await?;
+    std::fs::remove_dir_all(local_dir)?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_upload_directory_symlink_loop() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+{
+    let folder_name = Uuid::new_v4().to_string();
+
+    // Local directory with a link back to itself
+    let local_dir = std::env::temp_dir().join(&folder_name);
+    std::fs::create_dir_all(local_dir.join("sub"))?;
+    std::fs::write(local_dir.join("top.txt"), "top")?;
+    std::fs::write(local_dir.join("sub").join("middle.txt"), "middle")?;
+    std::os::unix::fs::symlink(&local_dir, local_dir.join("sub").join("loop"))?;
+
+    let pcloud = get_client().await?;
+    pcloud.create_folder("/", &folder_name)?.execute().await?;
+
+    let result = pcloud
+        .upload_directory(format!("/{}", folder_name), &local_dir)?
+        .upload()
+        .await?;
+
+    assert_eq!(1, result.folders);
+    assert_eq!(2, result.files.len());
+    assert!(
+        !pcloud
+            .folder_exists(format!("/{}/sub/loop", folder_name))
+            .await?
+    );
+
+    pcloud
+        .delete_folder(format!("/{}", folder_name))?
+        .delete_recursive()
+        .await?;
+    std::fs::remove_dir_all(local_dir)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_with_auth_token() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let pcloud = get_client().await?;
+    assert_eq!(PCloudResult::Ok, pcloud.get_user_info().await?.result);
+
+    // Only the overridden token is sent by the copy, so an invalid token must fail
+    let result = pcloud
+        .with_auth_token("invalid-token")
+        .get_user_info()
+        .await;
+    assert!(result.is_err());
+
+    // The original client is not affected
+    assert_eq!(PCloudResult::Ok, pcloud.get_user_info().await?.result);
+
+    // Same for OAuth clients, which must not send their Authorization header along with the overridden token
+    if let Ok(oauth2) = std::env::var("PCLOUD_OAUTH_TOKEN") {
+        let host = std::env::var("PCLOUD_HOST")?;
+        let oauth =
+            pcloud_async_api::pcloud_client::PCloudClient::with_oauth(&host, &oauth2).await?;
+        assert_eq!(PCloudResult::Ok, oauth.get_user_info().await?.result);
+
+        let result = oauth.with_auth_token("invalid-token").get_user_info().await;
+        assert!(result.is_err());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_audio_link_bitrate() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let pcloud = get_client().await?;
+
+    // Bit rates outside of 16 to 320 kbit/s are rejected instead of silently changed
+    assert!(pcloud.get_audio_link(1u64)?.with_bitrate(8).is_err());
+    assert!(pcloud.get_audio_link(1u64)?.with_bitrate(321).is_err());
+    assert!(pcloud.get_audio_link(1u64)?.with_bitrate(16).is_ok());
+    assert!(pcloud.get_audio_link(1u64)?.with_bitrate(320).is_ok());
+
+    Ok(())
+}